@@ -0,0 +1,127 @@
+//! Counts allocations, when the `count-allocs` feature is on, so the memory
+//! each stage of a solution uses can be measured
+
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    cell::Cell,
+};
+use serde::Serialize;
+
+/// The allocations made while running something
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct AllocStats {
+    /// The number of allocations and reallocations
+    pub allocations: u64,
+    /// The most memory it had allocated at once, beyond what was already
+    /// allocated when it started
+    pub peak_bytes: u64,
+}
+
+/// A thread's running totals
+#[derive(Copy, Clone)]
+struct Counts {
+    allocations: u64,
+    current: i64,
+    peak: i64,
+}
+
+thread_local! {
+    // Counted per thread so days solved at the same time don't mix
+    static COUNTS: Cell<Counts> = const { Cell::new(Counts { allocations: 0, current: 0, peak: 0 }) };
+}
+
+fn record(allocations: u64, bytes: i64) {
+    // This fails while the thread is being torn down, which doesn't matter
+    let _ = COUNTS.try_with(|counts| {
+        let mut c = counts.get();
+        c.allocations += allocations;
+        c.current += bytes;
+        c.peak = c.peak.max(c.current);
+        counts.set(c);
+    });
+}
+
+/// The system allocator, but counting allocations and bytes on each thread
+pub struct CountingAlloc;
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc(layout);
+        if !ptr.is_null() {
+            record(1, layout.size() as i64);
+        }
+        ptr
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc_zeroed(layout);
+        if !ptr.is_null() {
+            record(1, layout.size() as i64);
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+        record(0, -(layout.size() as i64));
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new_ptr = System.realloc(ptr, layout, new_size);
+        if !new_ptr.is_null() {
+            record(1, new_size as i64 - layout.size() as i64);
+        }
+        new_ptr
+    }
+}
+
+#[cfg(feature = "count-allocs")]
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
+/// Whether allocations are being counted
+pub fn enabled() -> bool {
+    cfg!(feature = "count-allocs")
+}
+
+/// Runs `f`, counting the allocations it makes on this thread
+///
+/// The stats are `None` if allocations aren't being counted.
+pub fn measure<T>(f: impl FnOnce() -> T) -> (T, Option<AllocStats>) {
+    if !enabled() {
+        return (f(), None);
+    }
+
+    let before = COUNTS.with(Cell::get);
+    COUNTS.with(|counts| counts.set(Counts { peak: before.current, ..before }));
+    let result = f();
+    let after = COUNTS.with(Cell::get);
+    // Put back the peak from before, in case this is inside another measurement
+    COUNTS.with(|counts| counts.set(Counts { peak: after.peak.max(before.peak), ..after }));
+
+    (result, Some(AllocStats {
+        allocations: after.allocations - before.allocations,
+        peak_bytes: (after.peak - before.current).max(0) as u64,
+    }))
+}
+
+#[cfg(all(test, feature = "count-allocs"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_measure() {
+        let (_, stats) = measure(|| {
+            let mut v = vec![0u8; 1000];
+            v.extend_from_slice(&[1; 1000]);
+            drop(v);
+            vec![0u8; 10]
+        });
+        let stats = stats.unwrap();
+        assert_eq!(stats.allocations, 3);
+        assert!((2000..4000).contains(&stats.peak_bytes), "{:?}", stats);
+
+        let (_, stats) = measure(|| 1 + 1);
+        assert_eq!(stats, Some(AllocStats::default()));
+    }
+}
@@ -0,0 +1,159 @@
+use std::{
+    collections::HashMap,
+    fmt::{Display, Formatter, Result as FmtResult},
+    fs,
+    str::FromStr,
+};
+use anyhow::{Context, Error, Result, bail};
+use serde::{Deserialize, Serialize};
+use crate::util::*;
+
+/// The file in the input directories that expected answers are kept in
+pub static ANSWERS_FILE: &str = "answers.txt";
+
+/// Expected answers, keyed by day, part and input name
+///
+/// Each line of the file is `<day> <part> <input name> <answer>`, separated by
+/// any whitespace, and each day, part and input can only have one. Blank lines
+/// and lines starting with `#` are ignored.
+#[derive(Debug, Default)]
+pub struct Answers(HashMap<(u32, Part, Box<str>), Box<str>>);
+
+impl Answers {
+    /// Loads the answers file, or no answers at all if there isn't one
+    pub fn load() -> Result<Self> {
+        let path = unwrap_or!(find_input_file(ANSWERS_FILE).ok(), return Ok(Self::default()));
+        fs::read_to_string(&path)
+            .with_context(|| format!("failed to read {}", path.display()))?
+            .parse()
+            .with_context(|| format!("failed to parse {}", path.display()))
+    }
+
+    pub fn get(&self, day: u32, part: Part, input: &str) -> Option<&str> {
+        self.0.get(&(day, part, input.into())).map(|answer| &**answer)
+    }
+
+    /// Checks `actual` against the expected answer, if there is one
+    pub fn check(
+        &self, day: u32, part: Part, input: &str, actual: &Result<String>
+    ) -> Status {
+        match (self.get(day, part, input), actual) {
+            (_, Err(_)) => Status::Fail,
+            (None, Ok(_)) => Status::Unknown,
+            (Some(expected), Ok(actual)) if expected == actual => Status::Pass,
+            (Some(_), Ok(_)) => Status::Fail,
+        }
+    }
+}
+
+impl FromStr for Answers {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let mut answers = HashMap::new();
+        // The line each answer was given on, to point out duplicates
+        let mut lines = HashMap::new();
+        let entries = s.lines()
+            .enumerate()
+            .map(|(i, line)| (i + 1, line.trim()))
+            .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'));
+        for (line_num, line) in entries {
+            let ((day, part, input), answer) = (|| -> Result<_> {
+                let mut fields = line.split_whitespace();
+                let mut next = |name| fields.next()
+                    .with_context(|| format!("missing {}", name));
+                let day: u32 = next("day")?.parse().context("invalid day")?;
+                let part: Part = next("part")?.parse()?;
+                let input: Box<str> = next("input name")?.into();
+                let answer: Box<str> = next("answer")?.into();
+                if fields.next().is_some() {
+                    bail!("more than one answer");
+                }
+                Ok(((day, part, input), answer))
+            })().with_context(|| format!("invalid answer on line {}: '{}'", line_num, line))?;
+
+            if let Some(first) = lines.insert((day, part, input.clone()), line_num) {
+                bail!(
+                    "duplicate answer on line {} for day {} part {} input {}, which line {} already gave",
+                    line_num, day, part, input, first,
+                );
+            }
+            answers.insert((day, part, input), answer);
+        }
+        Ok(Self(answers))
+    }
+}
+
+/// How a solver's answer compares to the expected one
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Status {
+    /// It matches
+    Pass,
+    /// It doesn't match, or the solver failed
+    Fail,
+    /// There's no expected answer to compare against
+    Unknown,
+}
+
+impl Status {
+    /// The lowercase name used in machine-readable output
+    pub fn name(self) -> &'static str {
+        match self {
+            Status::Pass => "pass",
+            Status::Fail => "fail",
+            Status::Unknown => "unknown",
+        }
+    }
+}
+
+impl Display for Status {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        match self {
+            Status::Pass => write!(f, "pass"),
+            Status::Fail => write!(f, "FAIL"),
+            Status::Unknown => write!(f, "unknown"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    static INPUT: &str = "\
+# day part input answer
+1 1 day1 514579
+
+1 2 day1 241861950";
+
+    #[test]
+    fn test_check() {
+        let answers: Answers = INPUT.parse().unwrap();
+        assert_eq!(answers.check(1, Part::One, "day1", &Ok("514579".into())), Status::Pass);
+        assert_eq!(answers.check(1, Part::Two, "day1", &Ok("514579".into())), Status::Fail);
+        assert_eq!(answers.check(1, Part::One, "other", &Ok("514579".into())), Status::Unknown);
+        assert_eq!(answers.check(2, Part::One, "day2", &Err(Error::msg("oops"))), Status::Fail);
+    }
+
+    #[test]
+    fn test_invalid_line() {
+        assert!("1 3 day1 42".parse::<Answers>().is_err());
+        assert!("1 1 day1".parse::<Answers>().is_err());
+        assert!("1 1 day1 42 43".parse::<Answers>().is_err());
+    }
+
+    #[test]
+    fn test_whitespace_between_fields() {
+        let answers: Answers = "1  1\t day1   42 ".parse().unwrap();
+        assert_eq!(answers.get(1, Part::One, "day1"), Some("42"));
+    }
+
+    #[test]
+    fn test_duplicate_answers() {
+        let e = format!("{}\n1 2  day1 42", INPUT).parse::<Answers>().unwrap_err();
+        assert_eq!(e.to_string(), "duplicate answer on line 5 for day 1 part 2 input day1, which line 4 already gave");
+        // The same day and part for a different input is fine
+        assert!(format!("{}\n1 2 day1/alice 42", INPUT).parse::<Answers>().is_ok());
+    }
+}
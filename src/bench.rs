@@ -0,0 +1,198 @@
+use std::{
+    fmt::{Display, Formatter, Result as FmtResult},
+    fs,
+    hint::black_box,
+    path::Path,
+    time::{Duration, Instant},
+};
+use anyhow::{Context, Error, Result};
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+use crate::{Day, util::*};
+
+/// A stage of a day's solution that can be timed on its own
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Stage {
+    Parse,
+    Part1,
+    Part2,
+}
+
+impl Display for Stage {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        match self {
+            Stage::Parse => write!(f, "parse"),
+            Stage::Part1 => write!(f, "part 1"),
+            Stage::Part2 => write!(f, "part 2"),
+        }
+    }
+}
+
+/// The timings of one stage of one day on one of its inputs, over many
+/// iterations
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct BenchResult {
+    pub day: u32,
+    /// The name of the input it was timed on
+    pub input: String,
+    pub stage: Stage,
+    pub iterations: usize,
+    pub min_ns: u64,
+    pub median_ns: u64,
+    pub mean_ns: u64,
+}
+
+impl BenchResult {
+    fn new(day: u32, input: &str, stage: Stage, mut samples: Vec<Duration>) -> Self {
+        samples.sort_unstable();
+        let nanos = |d: Duration| d.as_nanos() as u64;
+        let iterations = samples.len();
+        Self {
+            day,
+            input: input.into(),
+            stage,
+            iterations,
+            min_ns: samples.first().copied().map_or(0, nanos),
+            median_ns: samples.get(iterations / 2).copied().map_or(0, nanos),
+            mean_ns: nanos(samples.iter().sum::<Duration>() / iterations.max(1) as u32),
+        }
+    }
+
+    /// How much slower this is than `baseline`, as a percentage of its median
+    fn percent_slower_than(&self, baseline: &BenchResult) -> f64 {
+        (self.median_ns as f64 - baseline.median_ns as f64) / baseline.median_ns.max(1) as f64 * 100.0
+    }
+}
+
+/// Times `f` over `iterations` runs
+fn time<T>(iterations: usize, mut f: impl FnMut() -> T) -> Vec<Duration> {
+    (0..iterations).map(|_| {
+        let start = Instant::now();
+        black_box(f());
+        start.elapsed()
+    }).collect()
+}
+
+/// Times the parse step and each part of `day` on each of its inputs
+///
+/// Returns the timings for every input that could be read and solved, and
+/// the errors for the ones that couldn't.
+pub fn bench_day(day: &Day, iterations: usize) -> (Vec<BenchResult>, Vec<Error>) {
+    let mut results = Vec::new();
+    let mut errors = Vec::new();
+    for input_name in day.input_names() {
+        match bench_input(day, &input_name, iterations) {
+            Ok(input_results) => results.extend(input_results),
+            Err(e) => errors.push(e),
+        }
+    }
+    (results, errors)
+}
+
+/// Times the parse step and each part of `day` on the input named `input_name`
+fn bench_input(day: &Day, input_name: &str, iterations: usize) -> Result<Vec<BenchResult>> {
+    let input = get_input(input_name)?;
+    let context = || format!("failed to benchmark day {} on {}", day.number, input_name);
+
+    let parsed = day.parse(&input).map_err(|e| e.with_input(input_name)).with_context(context)?;
+    // Make sure the parts work before timing them
+    parsed.part1().with_context(context)?;
+    parsed.part2().with_context(context)?;
+
+    let result = |stage, samples| BenchResult::new(day.number, input_name, stage, samples);
+    Ok(vec![
+        result(Stage::Parse, time(iterations, || day.parse(&input))),
+        result(Stage::Part1, time(iterations, || parsed.part1())),
+        result(Stage::Part2, time(iterations, || parsed.part2())),
+    ])
+}
+
+pub fn write_results(path: &Path, results: &[BenchResult]) -> Result<()> {
+    let json = serde_json::to_string_pretty(results)?;
+    fs::write(path, json)
+        .with_context(|| format!("failed to write benchmark results to {}", path.display()))
+}
+
+pub fn read_results(path: &Path) -> Result<Vec<BenchResult>> {
+    let json = fs::read_to_string(path)
+        .with_context(|| format!("failed to read benchmark results from {}", path.display()))?;
+    serde_json::from_str(&json)
+        .with_context(|| format!("failed to parse benchmark results from {}", path.display()))
+}
+
+/// Prints `results` as a table, compared to `baseline` if there is one
+///
+/// Returns whether any stage got slower than the baseline by more than
+/// `threshold` percent.
+pub fn report(results: &[BenchResult], baseline: Option<&[BenchResult]>, threshold: f64) -> bool {
+    fn format_ns(ns: u64) -> String {
+        format!("{:.2?}", Duration::from_nanos(ns))
+    }
+
+    let mut regressed = false;
+    let rows = results.iter().map(|result| {
+        let mut row = vec![
+            result.day.to_string(),
+            result.input.clone(),
+            result.stage.to_string(),
+            format_ns(result.min_ns),
+            format_ns(result.median_ns),
+            format_ns(result.mean_ns),
+        ];
+        if let Some(baseline) = baseline {
+            let base = baseline.iter()
+                .find(|base| (base.day, &base.input, base.stage) == (result.day, &result.input, result.stage));
+            if let Some(base) = base {
+                let change = result.percent_slower_than(base);
+                let slower = change > threshold;
+                regressed |= slower;
+                row.push(format_ns(base.median_ns));
+                row.push(format!("{:+.1}%{}", change, if slower { " SLOWER" } else { "" }));
+            } else {
+                row.push("-".into());
+                row.push("-".into());
+            }
+        }
+        row
+    }).collect_vec();
+
+    let mut headers = vec!["Day", "Input", "Stage", "Min", "Median", "Mean"];
+    if baseline.is_some() {
+        headers.extend(&["Baseline", "Change"]);
+    }
+    print_table(&headers, &rows);
+    regressed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stats() {
+        let samples = [5, 1, 3, 2, 4].iter().map(|&ms| Duration::from_millis(ms)).collect();
+        let result = BenchResult::new(1, "day1", Stage::Parse, samples);
+        assert_eq!(result.iterations, 5);
+        assert_eq!(result.min_ns, 1_000_000);
+        assert_eq!(result.median_ns, 3_000_000);
+        assert_eq!(result.mean_ns, 3_000_000);
+    }
+
+    #[test]
+    fn test_percent_slower() {
+        let base = BenchResult::new(1, "day1", Stage::Part1, vec![Duration::from_millis(10)]);
+        let result = BenchResult::new(1, "day1", Stage::Part1, vec![Duration::from_millis(12)]);
+        assert!((result.percent_slower_than(&base) - 20.0).abs() < 1e-9);
+        assert!((base.percent_slower_than(&result) + 16.666).abs() < 0.001);
+    }
+
+    #[test]
+    fn test_json_round_trip() {
+        let results = vec![BenchResult::new(9, "day9", Stage::Part2, vec![Duration::from_micros(7)])];
+        let json = serde_json::to_string(&results).unwrap();
+        assert!(json.contains(r#""input":"day9","stage":"part2""#));
+        let parsed: Vec<BenchResult> = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed[0].median_ns, 7_000);
+    }
+}
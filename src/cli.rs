@@ -0,0 +1,406 @@
+//! The `aoc2020` command-line interface
+
+use std::{env, fs, path::PathBuf, process, time::SystemTime};
+use anyhow::{Context, Result, bail};
+use itertools::Itertools;
+use crate::{
+    DAYS, Day, bench, history, runner::{self, Format, PartResult}, scaffold, serve,
+    answers::{Answers, Status},
+    util::{InputMode, InputSource, Parts, Rng, print_table},
+};
+
+static USAGE: &str = "\
+Usage: aoc2020 run <day|all> [--part <1|2>] [--input <path|->] [--jobs <n>]
+                   [--format <text|json|csv>] [--strict]
+       aoc2020 verify [day|all] [--jobs <n>] [--format <text|json|csv>] [--strict]
+       aoc2020 bench [day|all] [--iterations <n>] [--output <json>]
+                     [--baseline <json>] [--threshold <percent>]
+       aoc2020 history [day|all]
+       aoc2020 new <day>
+       aoc2020 gen <day> [--size <n>] [--seed <n>] [--output <path>]
+       aoc2020 serve [--port <n>]
+
+Running more than one day solves them on worker threads and prints a summary.
+--format json prints one object per line and --format csv prints a header row,
+each with the day, part, input, answer, error, timings and status. Building
+with --features count-allocs adds each stage's allocation count and peak bytes.
+
+Inputs are read from the directory in $AOC2020_INPUT_DIR, ./inputs or this
+crate's inputs directory, unless --input gives a file or - for stdin. Named
+variants like inputs/day7/alice.txt are solved and benchmarked too, as input
+day7/alice, and can have their own expected answers in answers.txt. A byte
+order mark, CRLF line endings, trailing whitespace and blank lines at the end
+are cleaned up, with a note saying so; --strict makes them an error instead.
+
+run and verify add their results to a history file, $AOC2020_HISTORY or
+history.jsonl in this crate's directory. history shows each part's runs and
+flags any answer that changed for the same input.
+
+gen writes a random valid input to stdout or --output. Without --seed, the
+seed it picked is printed to stderr so the input can be generated again.
+
+serve answers HTTP requests on localhost, port 8020 by default. POST an input
+to /solve/<day> or /solve/<day>/<part> to get back JSON with each part's
+answer, timings and status, and where the input failed to parse if it did.
+GET /days lists the solved days.";
+
+enum Command {
+    Run {
+        days: Vec<&'static Day>,
+        parts: Parts,
+        source: InputSource,
+        jobs: usize,
+        format: Format,
+        mode: InputMode,
+    },
+    Verify { days: Vec<&'static Day>, jobs: usize, format: Format, mode: InputMode },
+    Bench {
+        days: Vec<&'static Day>,
+        iterations: usize,
+        output: Option<PathBuf>,
+        baseline: Option<PathBuf>,
+        threshold: f64,
+    },
+    History { days: Vec<&'static Day> },
+    New { day: u32 },
+    Gen { day: &'static Day, size: usize, seed: Option<u64>, output: Option<PathBuf> },
+    Serve { port: u16 },
+}
+
+impl Command {
+    fn from_args(mut args: impl Iterator<Item = String>) -> Result<Self> {
+        let cmd = args.next().context("no command given")?;
+        let command = match cmd.as_str() {
+            "run" => {
+                let days = select_days(&args.next().context("no day given")?)?;
+                let mut parts = Parts::Both;
+                let mut source = InputSource::Default;
+                let mut jobs = runner::default_jobs();
+                let mut format = Format::Text;
+                let mut mode = InputMode::Normalize;
+                while let Some(arg) = args.next() {
+                    match arg.as_str() {
+                        "--part" | "-p" => {
+                            parts = args.next().context("no part given")?.parse()?;
+                        }
+                        "--input" | "-i" => {
+                            source = args.next().context("no input path given")?.parse()?;
+                        }
+                        "--jobs" | "-j" => jobs = parse_jobs(args.next())?,
+                        "--format" | "-f" => format = parse_format(args.next())?,
+                        "--strict" => mode = InputMode::Strict,
+                        _ => bail!("unexpected argument '{}'", arg),
+                    }
+                }
+                if days.len() > 1 && source != InputSource::Default {
+                    bail!("--input can only be used when running a single day");
+                }
+                Command::Run { days, parts, source, jobs, format, mode }
+            }
+            "verify" => {
+                let mut days = DAYS.iter().collect();
+                let mut jobs = runner::default_jobs();
+                let mut format = Format::Text;
+                let mut mode = InputMode::Normalize;
+                while let Some(arg) = args.next() {
+                    match arg.as_str() {
+                        "--jobs" | "-j" => jobs = parse_jobs(args.next())?,
+                        "--format" | "-f" => format = parse_format(args.next())?,
+                        "--strict" => mode = InputMode::Strict,
+                        _ if !arg.starts_with('-') => days = select_days(&arg)?,
+                        _ => bail!("unexpected argument '{}'", arg),
+                    }
+                }
+                Command::Verify { days, jobs, format, mode }
+            }
+            "bench" => {
+                let mut days = DAYS.iter().collect();
+                let mut iterations = 100;
+                let mut output = None;
+                let mut baseline = None;
+                let mut threshold = 10.0;
+                while let Some(arg) = args.next() {
+                    let mut value = |name| args.next()
+                        .with_context(|| format!("no {} given", name));
+                    match arg.as_str() {
+                        "--iterations" | "-n" => {
+                            iterations = value("iteration count")?.parse()
+                                .context("invalid iteration count")?;
+                            if iterations == 0 { bail!("need at least 1 iteration"); }
+                        }
+                        "--output" | "-o" => output = Some(value("output path")?.into()),
+                        "--baseline" | "-b" => baseline = Some(value("baseline path")?.into()),
+                        "--threshold" | "-t" => {
+                            threshold = value("threshold")?.parse()
+                                .context("invalid threshold percentage")?;
+                        }
+                        _ if !arg.starts_with('-') => days = select_days(&arg)?,
+                        _ => bail!("unexpected argument '{}'", arg),
+                    }
+                }
+                Command::Bench { days, iterations, output, baseline, threshold }
+            }
+            "history" => {
+                let days = match args.next() {
+                    Some(arg) => select_days(&arg)?,
+                    None => DAYS.iter().collect(),
+                };
+                Command::History { days }
+            }
+            "new" => {
+                let day = args.next().context("no day given")?;
+                let day = day.parse()
+                    .with_context(|| format!("expected a day number but found '{}'", day))?;
+                if !(1..=25).contains(&day) { bail!("there's no day {} in Advent of Code", day); }
+                Command::New { day }
+            }
+            "gen" => {
+                let day = match select_days(&args.next().context("no day given")?)?[..] {
+                    [day] => day,
+                    _ => bail!("gen needs a single day"),
+                };
+                let mut size = 1000;
+                let mut seed = None;
+                let mut output = None;
+                while let Some(arg) = args.next() {
+                    let mut value = |name| args.next()
+                        .with_context(|| format!("no {} given", name));
+                    match arg.as_str() {
+                        "--size" | "-n" => {
+                            size = value("size")?.parse().context("invalid size")?;
+                        }
+                        "--seed" | "-s" => {
+                            seed = Some(value("seed")?.parse().context("invalid seed")?);
+                        }
+                        "--output" | "-o" => output = Some(value("output path")?.into()),
+                        _ => bail!("unexpected argument '{}'", arg),
+                    }
+                }
+                Command::Gen { day, size, seed, output }
+            }
+            "serve" => {
+                let mut port = 8020;
+                while let Some(arg) = args.next() {
+                    match arg.as_str() {
+                        "--port" | "-p" => {
+                            let value = args.next().context("no port given")?;
+                            port = value.parse()
+                                .with_context(|| format!("invalid port '{}'", value))?;
+                        }
+                        _ => bail!("unexpected argument '{}'", arg),
+                    }
+                }
+                Command::Serve { port }
+            }
+            _ => bail!("unknown command '{}'", cmd),
+        };
+
+        if let Some(arg) = args.next() {
+            bail!("unexpected argument '{}'", arg);
+        }
+        Ok(command)
+    }
+}
+
+/// Finds the days selected by `arg`, which is either a day number or "all"
+fn select_days(arg: &str) -> Result<Vec<&'static Day>> {
+    if arg == "all" {
+        return Ok(DAYS.iter().collect());
+    }
+
+    let number: u32 = arg.parse()
+        .with_context(|| format!("expected a day number or 'all' but found '{}'", arg))?;
+    let day = DAYS.iter().find(|day| day.number == number)
+        .with_context(|| format!("day {} hasn't been solved yet", number))?;
+    Ok(vec![day])
+}
+
+fn parse_jobs(arg: Option<String>) -> Result<usize> {
+    let jobs = arg.context("no job count given")?.parse()
+        .context("invalid job count")?;
+    if jobs == 0 { bail!("need at least 1 job"); }
+    Ok(jobs)
+}
+
+/// Adds `results` to the history file, warning instead of failing if it can't
+fn record_history(results: &[PartResult]) {
+    if let Err(e) = history::append(&history::history_path(), results, history::now()) {
+        eprintln!("warning: {:#}", e);
+    }
+}
+
+/// Prints a note for each input in `results` that had to be normalized
+fn note_normalized(results: &[PartResult]) {
+    let normalized = results.iter()
+        .filter(|result| !result.normalization.is_empty())
+        .unique_by(|result| (result.day, &result.input));
+    for result in normalized {
+        eprintln!("note: normalized {}: {}", result.input, result.normalization);
+    }
+}
+
+fn parse_format(arg: Option<String>) -> Result<Format> {
+    arg.context("no format given")?.parse()
+}
+
+/// Checks every part of `days` against the expected answers, printing a table
+/// or the results in `format`
+///
+/// Returns whether all of them passed or had no expected answer.
+fn verify(days: &[&Day], jobs: usize, format: Format, mode: InputMode) -> Result<bool> {
+    let answers = Answers::load()?;
+    let results = runner::run_all(days, Parts::Both, &answers, mode, jobs);
+    note_normalized(&results);
+    record_history(&results);
+    if format != Format::Text {
+        return runner::print_results(&results, format);
+    }
+
+    let rows: Vec<_> = results.iter().map(|result| vec![
+        result.day.to_string(),
+        result.part.to_string(),
+        result.input.clone(),
+        answers.get(result.day, result.part, &result.input).unwrap_or("?").to_owned(),
+        result.answer_summary(),
+        result.status.to_string(),
+    ]).collect();
+
+    print_table(&["Day", "Part", "Input", "Expected", "Actual", "Status"], &rows);
+    Ok(results.iter().all(|result| result.status != Status::Fail))
+}
+
+/// Parses one day's input, or each of its inputs if there are variants, and
+/// solves the requested parts, printing the answers and how they compare to
+/// the expected ones, or the results in `format`
+///
+/// Returns whether none of them failed, like running every day does.
+fn run(
+    day: &Day, parts: Parts, source: &InputSource, format: Format, mode: InputMode
+) -> Result<bool> {
+    let answers = Answers::load()?;
+    let input_names = match source {
+        InputSource::Default => day.input_names(),
+        _ => vec![day.input_name()],
+    };
+    let mut results = Vec::new();
+    for input_name in &input_names {
+        let (input, normalization) = source.read(input_name, mode)?;
+        let mut input_results = runner::solve(day, &source.name(input_name), &input, parts, &answers);
+        for result in &mut input_results {
+            result.normalization = normalization.clone();
+        }
+        results.extend(input_results);
+    }
+    note_normalized(&results);
+    record_history(&results);
+    if format != Format::Text {
+        return runner::print_results(&results, format);
+    }
+    for result in &results {
+        let label = if input_names.len() > 1 {
+            format!("Day {} part {} ({})", day.number, result.part, result.input)
+        } else {
+            format!("Day {} part {}", day.number, result.part)
+        };
+        let answer = match &result.answer {
+            Ok(answer) => answer.clone(),
+            Err(e) => format!("error: {:#}", e),
+        };
+        let mut notes = Vec::new();
+        match result.status {
+            Status::Pass => notes.push("pass".to_owned()),
+            Status::Fail if result.answer.is_ok() => notes.push(format!(
+                "FAIL, expected {}",
+                answers.get(result.day, result.part, &result.input).unwrap_or("?"),
+            )),
+            _ => {}
+        }
+        notes.extend(result.alloc_summary());
+        if notes.is_empty() {
+            println!("{}: {}", label, answer);
+        } else {
+            println!("{}: {} ({})", label, answer, notes.join("; "));
+        }
+    }
+    Ok(results.iter().all(|result| result.status != Status::Fail))
+}
+
+/// Runs the command given by the process's arguments
+pub fn main() -> Result<()> {
+    let command = unwrap_or!(Command::from_args(env::args().skip(1)), e => {
+        eprintln!("error: {:#}\n\n{}", e, USAGE);
+        process::exit(2);
+    });
+
+    match command {
+        Command::Run { days, parts, source, jobs, format, mode } => {
+            let ok = if let [day] = days[..] {
+                run(day, parts, &source, format, mode)?
+            } else {
+                let results = runner::run_all(&days, parts, &Answers::load()?, mode, jobs);
+                note_normalized(&results);
+                record_history(&results);
+                runner::print_results(&results, format)?
+            };
+            if !ok {
+                process::exit(1);
+            }
+        }
+        Command::Verify { days, jobs, format, mode } => {
+            if !verify(&days, jobs, format, mode)? {
+                process::exit(1);
+            }
+        }
+        Command::Bench { days, iterations, output, baseline, threshold } => {
+            let baseline = baseline.as_deref().map(bench::read_results).transpose()?;
+            let mut results = Vec::new();
+            let mut failed = false;
+            for day in days {
+                let (day_results, errors) = bench::bench_day(day, iterations);
+                results.extend(day_results);
+                for e in errors {
+                    eprintln!("error: {:#}", e);
+                    failed = true;
+                }
+            }
+            if let Some(output) = output {
+                bench::write_results(&output, &results)?;
+            }
+            if bench::report(&results, baseline.as_deref(), threshold) || failed {
+                process::exit(1);
+            }
+        }
+        Command::History { days } => {
+            let entries = history::load(&history::history_path())?.into_iter()
+                .filter(|entry| days.iter().any(|day| day.number == entry.day))
+                .collect::<Vec<_>>();
+            if entries.is_empty() {
+                println!("No runs recorded yet");
+            } else if !history::report(&entries, history::now()) {
+                process::exit(1);
+            }
+        }
+        Command::New { day } => {
+            for path in scaffold::new_day(scaffold::crate_root(), day)? {
+                println!("Wrote {}", path.display());
+            }
+        }
+        Command::Gen { day, size, seed, output } => {
+            let seed = seed.unwrap_or_else(|| {
+                let seed = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH)
+                    .map_or(0, |since| since.as_nanos() as u64);
+                eprintln!("seed: {}", seed);
+                seed
+            });
+            let input = day.generate(&mut Rng::new(seed), size);
+            match output {
+                Some(path) => fs::write(&path, input + "\n")
+                    .with_context(|| format!("failed to write {}", path.display()))?,
+                None => println!("{}", input),
+            }
+        }
+        Command::Serve { port } => serve::serve(port)?,
+    }
+
+    Ok(())
+}
@@ -1,58 +1,794 @@
-use std::str::FromStr;
+use std::{
+    any::type_name,
+    collections::{HashMap, HashSet},
+    convert::TryFrom,
+    fmt::{self, Debug, Display, Formatter},
+    hash::Hash,
+    iter, mem,
+    num::ParseIntError,
+    ops::{Range, RangeInclusive},
+    str::FromStr,
+};
+use anyhow::{Context, Result};
+use itertools::{Either, Itertools};
 use crate::util::*;
 
-pub(crate) fn day1() {
-    let report = get_input("day1").parse().unwrap();
-    part1(&report);
-    part2(&report);
+pub struct Day1;
+
+/// What the entries in the puzzle need to sum to
+pub const TARGET: i32 = 2020;
+
+impl Solution for Day1 {
+    type Input<'a> = ExpenseReport;
+    type Answer = i32;
+
+    fn parse(input: &str) -> Result<ExpenseReport, ParseError> {
+        input.parse()
+    }
+
+    fn part1(report: &ExpenseReport) -> Result<i32> {
+        product_of_entries_with_sum(report, 2, TARGET)
+    }
+
+    fn part2(report: &ExpenseReport) -> Result<i32> {
+        product_of_entries_with_sum(report, 3, TARGET)
+    }
+
+    fn generate(rng: &mut Rng, size: usize) -> String {
+        // The planted entries are the only ones under 1010, and no filler entry
+        // completes another sum with them, so both answers are unique
+        let a = rng.range(300..500);
+        let (b, c) = (rng.range(100..200), rng.range(200..300));
+        let mut entries = vec![a, 2020 - a, b, c, 2020 - b - c];
+        let banned: HashSet<i64> = [a, b, c, a + b, a + c, b + c, 2 * a, 2 * b, 2 * c].iter()
+            .map(|sum| 2020 - sum)
+            .collect();
+        while entries.len() < size {
+            let entry = rng.range(1011..2020);
+            if !banned.contains(&entry) {
+                entries.push(entry);
+            }
+        }
+        rng.shuffle(&mut entries);
+        entries.iter().join("\n")
+    }
 }
 
-fn part1(report: &ExpenseReport) {
-    let answer = product_of_2_entries_with_sum_2020(
-        report
-    ).unwrap();
+/// Finds `k` entries that sum to `target` and multiplies them together
+pub fn product_of_entries_with_sum<T: Amount>(report: &ExpenseReport<T>, k: usize, target: T)
+-> Result<T> {
+    let sum = report.find_sum(k, target)?
+        .with_context(|| format!("couldn't find {} entries that sum to {}", k, target))?;
+    Ok(sum.product()?)
+}
 
-    println!("The product of the two entries that sum to 2020 is {}", answer);
-    assert_eq!(answer, 888331);
+/// An integer type expense entries can be
+///
+/// Sums of them are worked out in `i128` and products in the type itself,
+/// both checked so they can't silently overflow.
+pub trait Amount:
+    Copy + Ord + Hash + Debug + Display + FromStr<Err = ParseIntError> + Send + Sync + 'static
+{
+    const ONE: Self;
+
+    /// Widens this to add entries up without overflowing
+    fn to_i128(self) -> i128;
+
+    fn checked_mul(self, other: Self) -> Option<Self>;
+}
+
+macro_rules! impl_amount {
+    ($($t:ty),*) => {$(
+        impl Amount for $t {
+            const ONE: Self = 1;
+
+            fn to_i128(self) -> i128 {
+                self as i128
+            }
+
+            fn checked_mul(self, other: Self) -> Option<Self> {
+                <$t>::checked_mul(self, other)
+            }
+        }
+    )*};
 }
 
-fn part2(report: &ExpenseReport) {
-    let answer = product_of_3_entries_with_sum_2020(
-        report
-    ).unwrap();
+impl_amount!(i32, i64, i128, u64);
 
-    println!("The product of the three entries that sum to 2020 is {}", answer);
-    assert_eq!(answer, 130933530);
+/// What was being done to some entries when it overflowed
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Operation {
+    /// Adding them up, in `i128`
+    Sum,
+    /// Multiplying them together, in their own type
+    Product,
 }
 
-fn product_of_2_entries_with_sum_2020(ExpenseReport(entries): &ExpenseReport)
--> Result<i32, &'static str> {
-    entries.iter().copied().find_map(|n1|
-        entries.iter().copied().find(|n2| n1 + n2 == 2020)
-            .map(|n2| n1 * n2))
-        .ok_or("couldn't find any entries that sum to 2020")
+/// The error when a sum or product of some entries doesn't fit in the type
+/// it's worked out in
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Overflow<T> {
+    pub operation: Operation,
+    /// The entries being added or multiplied, up to where it overflowed
+    pub values: Vec<T>,
 }
 
-fn product_of_3_entries_with_sum_2020(ExpenseReport(entries): &ExpenseReport)
--> Result<i32, &'static str> {
-    entries.iter().copied()
-        .find_map(|n1| entries.iter().copied()
-            .find_map(|n2| entries.iter().copied()
-                .find(|n3| n1 + n2 + n3 == 2020).map(|n3| (n2, n3)))
-            .map(|(n2, n3)| n1 * n2 * n3))
-        .ok_or("couldn't find any entries that sum to 2020")
+impl<T: Amount> Display for Overflow<T> {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        let (op, ty) = match self.operation {
+            Operation::Sum => (" + ", "i128"),
+            Operation::Product => (" * ", type_name::<T>()),
+        };
+        write!(f, "{} overflows {}", self.values.iter().join(op), ty)
+    }
 }
 
+impl<T: Amount> std::error::Error for Overflow<T> {}
+
 #[derive(Clone, Debug)]
-struct ExpenseReport(Vec<i32>);
+pub struct ExpenseReport<T = i32>(Vec<T>);
+
+/// Some of the entries in an [`ExpenseReport`], in the order they're listed
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Selection<T = i32> {
+    /// Where each entry is in the report, counting from 0
+    pub indices: Vec<usize>,
+    pub values: Vec<T>,
+}
+
+impl<T: Amount> Selection<T> {
+    /// Multiplies the entries together
+    pub fn product(&self) -> Result<T, Overflow<T>> {
+        self.values.iter().try_fold(T::ONE, |product, &value| product.checked_mul(value))
+            .ok_or_else(|| Overflow { operation: Operation::Product, values: self.values.clone() })
+    }
+}
+
+impl<T: Amount> ExpenseReport<T> {
+    pub fn new(entries: Vec<T>) -> Self {
+        Self(entries)
+    }
+
+    pub fn entries(&self) -> &[T] {
+        &self.0
+    }
+
+    /// Finds `k` entries that sum to `target`
+    ///
+    /// This is the first of [`sums`](Self::sums).
+    pub fn find_sum(&self, k: usize, target: T) -> Result<Option<Selection<T>>, Overflow<T>> {
+        self.sums(k, target).next().transpose()
+    }
+
+    /// Every set of `k` entries that sums to `target`
+    ///
+    /// Each entry is used at most once in a set, though equal entries on
+    /// different lines can be used together, and each set of lines comes up
+    /// once.
+    ///
+    /// The entries are sorted, and entries that are too small or too big to
+    /// reach `target` are skipped. Up to 3 entries, the last one of each sum
+    /// is looked up in a hash map, which takes O(n^(k-1)) time at worst
+    /// instead of O(n^k). From 4 on, the search meets in the middle: each set
+    /// of the first k/2 entries is kept in a hash map by its sum, and looked
+    /// up from each set of the rest, which takes O(n^(k-k/2)) time and
+    /// O(n^(k/2)) memory at worst. The sets are found as they're asked for.
+    ///
+    /// Sums are worked out in `i128`, so only `i128` entries can overflow
+    /// them. If one does part way through adding up a set, that comes back
+    /// as an [`Overflow`] error and the iterator stops.
+    pub fn sums(&self, k: usize, target: T) -> Sums<'_, T> {
+        Sums {
+            report: self,
+            search: SumSearch::new(&self.0),
+            k,
+            target: target.to_i128(),
+            prefix: Vec::with_capacity(k),
+            last: 0..0,
+            started: false,
+            halves: if k >= MEET_FROM { Some(Halves::default()) } else { None },
+            done: false,
+        }
+    }
+
+    /// How many sets of `k` entries sum to `target`, without building them
+    ///
+    /// This is how many [`sums`](Self::sums) there are, so anything over 1
+    /// means the answer is ambiguous.
+    pub fn count_sums(&self, k: usize, target: T) -> Result<u64, Overflow<T>> {
+        let search = SumSearch::new(&self.0);
+        if k >= MEET_FROM {
+            search.count_halves(k, target.to_i128())
+        } else {
+            search.count(k, 0, 0, target.to_i128(), &mut Vec::with_capacity(k))
+        }
+    }
+
+    /// Sets up queries for subsets of any size that sum to a target, using
+    /// at most `memory_limit` bytes for the tables and a widened copy of the entries
+    pub fn subset_sums(&self, memory_limit: usize) -> SubsetSums<'_, T> {
+        SubsetSums { report: self, memory_limit }
+    }
+
+    fn select(&self, indices: impl Iterator<Item = usize>) -> Selection<T> {
+        let indices = indices.sorted().collect_vec();
+        let values = indices.iter().map(|&i| self.0[i]).collect();
+        Selection { indices, values }
+    }
+}
+
+/// The fewest entries in a sum to search for by meeting in the middle
+const MEET_FROM: usize = 4;
+
+/// Adds up `values`, or returns `None` if that overflows
+fn checked_sum<T: Amount>(values: &[T]) -> Option<i128> {
+    values.iter().try_fold(0i128, |sum, value| sum.checked_add(value.to_i128()))
+}
+
+/// The entries, sorted and indexed to search them for sums
+struct SumSearch<T> {
+    /// The entries in ascending order
+    sorted: Vec<T>,
+    /// The index in the report of each sorted entry
+    indices: Vec<usize>,
+    /// The positions in `sorted` that hold each value
+    runs: HashMap<i128, Range<usize>>,
+}
+
+impl<T: Amount> SumSearch<T> {
+    fn new(entries: &[T]) -> Self {
+        let indices = (0..entries.len()).sorted_by_key(|&i| entries[i]).collect_vec();
+        let sorted = indices.iter().map(|&i| entries[i]).collect_vec();
+        let mut runs = HashMap::new();
+        for (pos, value) in sorted.iter().enumerate() {
+            runs.entry(value.to_i128()).or_insert(pos..pos).end = pos + 1;
+        }
+        Self { sorted, indices, runs }
+    }
+
+    fn value(&self, pos: usize) -> i128 {
+        self.sorted[pos].to_i128()
+    }
+
+    /// The error for when the entries at `positions` overflow adding up
+    fn overflow(&self, positions: &[usize]) -> Overflow<T> {
+        let values = positions.iter().map(|&pos| self.sorted[pos]).collect();
+        Overflow { operation: Operation::Sum, values }
+    }
+
+    /// The first position from `from` on that could start the last `k`
+    /// entries of a sum to `target`, with the rest of them after it, when the
+    /// entries before them add up to `sum`
+    ///
+    /// There are none past the first start whose smallest possible sum is too
+    /// big, since the entries are sorted. Bounds that overflow aren't used.
+    fn next_start(&self, from: usize, k: usize, sum: i128, target: i128) -> Option<usize> {
+        let len = self.sorted.len();
+        let last_start = len.checked_sub(k)?;
+        // The biggest the other k - 1 entries could sum to
+        let biggest_rest = checked_sum(&self.sorted[len - (k - 1)..]);
+        (from..=last_start)
+            .take_while(|&pos| {
+                let smallest = checked_sum(&self.sorted[pos..pos + k])
+                    .and_then(|smallest| sum.checked_add(smallest));
+                smallest.is_none_or(|smallest| smallest <= target)
+            })
+            .find(|&pos| {
+                let biggest = biggest_rest
+                    .and_then(|rest| sum.checked_add(self.value(pos))?.checked_add(rest));
+                biggest.is_none_or(|biggest| biggest >= target)
+            })
+    }
+
+    /// The positions from `from` on that hold `value`
+    fn positions_of(&self, value: i128, from: usize) -> Range<usize> {
+        self.runs.get(&value).map_or(0..0, |run| run.start.max(from)..run.end.max(from))
+    }
+
+    /// Counts the sets of `k` positions from `from` on that, added to the
+    /// entries at `chosen` which sum to `sum`, make `target`
+    fn count(&self, k: usize, from: usize, sum: i128, target: i128, chosen: &mut Vec<usize>)
+    -> Result<u64, Overflow<T>> {
+        match k {
+            0 => Ok(u64::from(sum == target)),
+            // No entry is outside `i128`, so there's nothing to find if this overflows
+            1 => Ok(target.checked_sub(sum)
+                .map_or(0, |last| self.positions_of(last, from).len() as u64)),
+            _ => {
+                let mut count = 0;
+                let mut from = from;
+                while let Some(pos) = self.next_start(from, k, sum, target) {
+                    chosen.push(pos);
+                    let sum = sum.checked_add(self.value(pos)).ok_or_else(|| self.overflow(chosen))?;
+                    count += self.count(k - 1, pos + 1, sum, target, chosen)?;
+                    chosen.pop();
+                    from = pos + 1;
+                }
+                Ok(count)
+            }
+        }
+    }
+
+    /// Calls `f` with each set of positions made by adding `m` more from
+    /// `from..to` to `chosen`, which sum to `sum`, along with what they add
+    /// up to
+    fn combinations(
+        &self, from: usize, to: usize, m: usize, chosen: &mut Vec<usize>, sum: i128,
+        f: &mut impl FnMut(&[usize], i128) -> Result<(), Overflow<T>>,
+    ) -> Result<(), Overflow<T>> {
+        if m == 0 {
+            return f(chosen, sum);
+        }
+        for pos in from..to.saturating_sub(m - 1) {
+            chosen.push(pos);
+            let sum = sum.checked_add(self.value(pos)).ok_or_else(|| self.overflow(chosen))?;
+            self.combinations(pos + 1, to, m - 1, chosen, sum, f)?;
+            chosen.pop();
+        }
+        Ok(())
+    }
+
+    /// Whether no sum of `k` entries to `target` can have its second half
+    /// start at `boundary` or after, meeting in the middle
+    fn past_last_boundary(&self, boundary: usize, k: usize, target: i128) -> bool {
+        let (left_len, right_len) = (k / 2, k - k / 2);
+        if boundary + right_len > self.sorted.len() {
+            return true;
+        }
+        // The smallest sum of them, which only grows with the boundary
+        let smallest = checked_sum(&self.sorted[..left_len])
+            .zip(checked_sum(&self.sorted[boundary..boundary + right_len]))
+            .and_then(|(left, right)| left.checked_add(right));
+        boundary >= left_len && smallest.is_some_and(|smallest| smallest > target)
+    }
+
+    /// Whether a sum of `k` entries to `target` could have its second half
+    /// start at `boundary`, meeting in the middle
+    fn could_meet_at(&self, boundary: usize, k: usize, target: i128) -> bool {
+        let (left_len, right_len) = (k / 2, k - k / 2);
+        if boundary < left_len {
+            return false;
+        }
+        let biggest = checked_sum(&self.sorted[boundary - left_len..boundary])
+            .zip(checked_sum(&self.sorted[self.sorted.len() - right_len..]))
+            .and_then(|(left, right)| left.checked_add(right));
+        biggest.is_none_or(|biggest| biggest >= target)
+    }
+
+    /// The sums of first halves ending at `boundary` that some second half
+    /// after it could make up to `target`, meeting in the middle, or `None`
+    /// if there's no room for a second half
+    fn useful_first_halves(&self, boundary: usize, k: usize, target: i128)
+    -> Option<RangeInclusive<i128>> {
+        let right_len = k - k / 2;
+        let len = self.sorted.len();
+        let smallest_right = self.sorted.get(boundary + 1..boundary + 1 + right_len)
+            .and_then(checked_sum);
+        let biggest_right = checked_sum(&self.sorted[len.saturating_sub(right_len)..]);
+        let lo = biggest_right.and_then(|right| target.checked_sub(right)).unwrap_or(i128::MIN);
+        if boundary + right_len >= len {
+            return None;
+        }
+        let hi = smallest_right.and_then(|right| target.checked_sub(right)).unwrap_or(i128::MAX);
+        Some(lo..=hi)
+    }
+
+    /// Counts the sets of `k` entries that sum to `target` by meeting in the
+    /// middle, like [`Halves`] does but only keeping how many sets of the
+    /// first half make each sum
+    fn count_halves(&self, k: usize, target: i128) -> Result<u64, Overflow<T>> {
+        let (left_len, right_len) = (k / 2, k - k / 2);
+        let mut left_counts: HashMap<i128, u64> = HashMap::new();
+        let mut count = 0;
+        for boundary in 0..self.sorted.len() {
+            if self.past_last_boundary(boundary, k, target) {
+                break;
+            }
+            let mut chosen = vec![boundary];
+            let first = self.value(boundary);
+            if self.could_meet_at(boundary, k, target) {
+                let len = self.sorted.len();
+                self.combinations(boundary + 1, len, right_len - 1, &mut chosen, first, &mut |_, sum| {
+                    let rest = target.checked_sub(sum);
+                    count += rest.and_then(|rest| left_counts.get(&rest)).copied().unwrap_or(0);
+                    Ok(())
+                })?;
+            }
+            let useful = unwrap_or!(self.useful_first_halves(boundary, k, target), continue);
+            self.combinations(0, boundary, left_len - 1, &mut chosen, first, &mut |_, sum| {
+                if useful.contains(&sum) {
+                    *left_counts.entry(sum).or_insert(0) += 1;
+                }
+                Ok(())
+            })?;
+        }
+        Ok(count)
+    }
+}
+
+/// A search for sums of at least [`MEET_FROM`] entries that meets in the
+/// middle
+///
+/// The positions of each sum, in order, are split after the first k/2. Going
+/// through each position as the `boundary` where the second half starts,
+/// every second half starting there is looked up in a table of the first
+/// halves that end before it. Then the first halves ending there are added.
+#[derive(Default)]
+struct Halves {
+    /// The positions of every first half ending before `boundary` that could
+    /// still be part of a sum, one after the other, by what they add up to
+    ///
+    /// These are `u32`s to save memory, since a report long enough to need
+    /// more would never fit anyway.
+    left: HashMap<i128, Vec<u32>>,
+    boundary: usize,
+    /// The positions of the sums found but not returned yet
+    found: Vec<Vec<usize>>,
+}
+
+impl Halves {
+    /// The positions of the next sum of `k` entries to `target`
+    fn next<T: Amount>(&mut self, search: &SumSearch<T>, k: usize, target: i128)
+    -> Option<Result<Vec<usize>, Overflow<T>>> {
+        loop {
+            if let Some(found) = self.found.pop() {
+                return Some(Ok(found));
+            }
+            if search.past_last_boundary(self.boundary, k, target) {
+                return None;
+            }
+            if let Err(e) = self.step(search, k, target) {
+                return Some(Err(e));
+            }
+        }
+    }
+
+    /// Finds the sums whose second half starts at `boundary`, then adds the
+    /// first halves ending there and moves on
+    fn step<T: Amount>(&mut self, search: &SumSearch<T>, k: usize, target: i128)
+    -> Result<(), Overflow<T>> {
+        let (left_len, right_len) = (k / 2, k - k / 2);
+        let boundary = self.boundary;
+        self.boundary += 1;
+        let mut chosen = vec![boundary];
+        let first = search.value(boundary);
+
+        if search.could_meet_at(boundary, k, target) {
+            let (left, found) = (&self.left, &mut self.found);
+            let len = search.sorted.len();
+            search.combinations(boundary + 1, len, right_len - 1, &mut chosen, first, &mut |right, sum| {
+                let lefts = target.checked_sub(sum).and_then(|rest| left.get(&rest));
+                for left in lefts.into_iter().flat_map(|lefts| lefts.chunks(left_len)) {
+                    found.push(left.iter().map(|&pos| pos as usize).chain(right.iter().copied()).collect());
+                }
+                Ok(())
+            })?;
+            // So they come out in the order they were found
+            found.reverse();
+        }
+
+        let useful = unwrap_or!(search.useful_first_halves(boundary, k, target), return Ok(()));
+        let left = &mut self.left;
+        search.combinations(0, boundary, left_len - 1, &mut chosen, first, &mut |positions, sum| {
+            if useful.contains(&sum) {
+                left.entry(sum).or_default().extend(positions.iter().map(|&pos| pos as u32));
+            }
+            Ok(())
+        })
+    }
+}
+
+/// An iterator over the sets of entries that sum to a target, from
+/// [`ExpenseReport::sums`]
+pub struct Sums<'a, T> {
+    report: &'a ExpenseReport<T>,
+    search: SumSearch<T>,
+    k: usize,
+    target: i128,
+    /// The positions of the first k - 1 entries of the sums being found, each
+    /// after the one before it so no position is chosen twice
+    prefix: Vec<usize>,
+    /// The positions left that complete the sum of `prefix`
+    last: Range<usize>,
+    started: bool,
+    /// The search for sums of at least [`MEET_FROM`] entries, instead of
+    /// `prefix` and `last`
+    halves: Option<Halves>,
+    /// Whether it's stopped, after an overflow
+    done: bool,
+}
+
+impl<T: Amount> Sums<'_, T> {
+    /// Moves on to the next `prefix` that could be part of a sum, depth first,
+    /// returning false once there are none left
+    fn next_prefix(&mut self) -> Result<bool, Overflow<T>> {
+        let mut from = if mem::replace(&mut self.started, true) {
+            match self.prefix.pop() {
+                Some(pos) => pos + 1,
+                None => return Ok(false),
+            }
+        } else {
+            0
+        };
+        while self.prefix.len() < self.k - 1 {
+            let k = self.k - self.prefix.len();
+            match self.search.next_start(from, k, self.prefix_sum()?, self.target) {
+                Some(pos) => {
+                    self.prefix.push(pos);
+                    from = pos + 1;
+                }
+                None => match self.prefix.pop() {
+                    Some(pos) => from = pos + 1,
+                    None => return Ok(false),
+                },
+            }
+        }
+        Ok(true)
+    }
+
+    /// Moves on to the next `prefix` and the positions that complete it,
+    /// returning false once there are none left
+    fn advance(&mut self) -> Result<bool, Overflow<T>> {
+        if !self.next_prefix()? {
+            return Ok(false);
+        }
+        let from = self.prefix.last().map_or(0, |pos| pos + 1);
+        let sum = self.prefix_sum()?;
+        // No entry is outside `i128`, so there's nothing to find if this overflows
+        self.last = self.target.checked_sub(sum)
+            .map_or(0..0, |last| self.search.positions_of(last, from));
+        Ok(true)
+    }
 
-impl FromStr for ExpenseReport {
-    type Err = &'static str;
+    /// What the entries in `prefix` add up to, smallest first
+    fn prefix_sum(&self) -> Result<i128, Overflow<T>> {
+        let mut sum = 0i128;
+        for (i, &pos) in self.prefix.iter().enumerate() {
+            sum = sum.checked_add(self.search.value(pos))
+                .ok_or_else(|| self.search.overflow(&self.prefix[..=i]))?;
+        }
+        Ok(sum)
+    }
+}
+
+impl<T: Amount> Iterator for Sums<'_, T> {
+    type Item = Result<Selection<T>, Overflow<T>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        if self.k == 0 {
+            self.done = true;
+            return if self.target == 0 { Some(Ok(self.report.select(iter::empty()))) } else { None };
+        }
+        if let Some(halves) = &mut self.halves {
+            let found = halves.next(&self.search, self.k, self.target)?;
+            self.done = found.is_err();
+            let indices = &self.search.indices;
+            return Some(found.map(|found| self.report.select(found.into_iter().map(|pos| indices[pos]))));
+        }
+        loop {
+            if let Some(last) = self.last.next() {
+                let positions = self.prefix.iter().copied().chain(iter::once(last));
+                return Some(Ok(self.report.select(positions.map(|pos| self.search.indices[pos]))));
+            }
+            match self.advance() {
+                Ok(true) => {}
+                Ok(false) => return None,
+                Err(e) => {
+                    self.done = true;
+                    return Some(Err(e));
+                }
+            }
+        }
+    }
+}
+
+/// The most memory subset sum tables use unless told otherwise
+pub const DEFAULT_MEMORY_LIMIT: usize = 256 << 20;
+
+/// Why a subset sum query couldn't be answered
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SubsetSumError {
+    /// The table would need `needed` bytes, more than the limit
+    TooBig { needed: u128, limit: usize },
+    /// There are at least `u64::MAX` subsets
+    TooMany,
+}
+
+impl Display for SubsetSumError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Self::TooBig { needed, limit } => write!(
+                f, "a table of subset sums would need {} bytes, over the limit of {}", needed, limit
+            ),
+            Self::TooMany => write!(f, "too many subsets to count"),
+        }
+    }
+}
+
+impl std::error::Error for SubsetSumError {}
+
+/// Queries for subsets of an [`ExpenseReport`]'s entries that sum to a
+/// target, from [`ExpenseReport::subset_sums`]
+///
+/// These go through a table of every sum the entries can make on the way to
+/// the target, one entry at a time, so they take time and memory in
+/// proportion to how far apart the entries' sums can be rather than to the
+/// number of subsets. The empty subset sums to zero.
+pub struct SubsetSums<'a, T> {
+    report: &'a ExpenseReport<T>,
+    memory_limit: usize,
+}
+
+/// A mark in [`SubsetSums::find`]'s table for sums not made yet
+const UNREACHED: u32 = u32::MAX;
+/// A mark in [`SubsetSums::find`]'s table for the empty subset's sum
+const EMPTY: u32 = u32::MAX - 1;
+
+impl<T: Amount> SubsetSums<'_, T> {
+    /// Finds any subset of the entries that sums to `target`
+    pub fn find(&self, target: T) -> Result<Option<Selection<T>>, SubsetSumError> {
+        let table = unwrap_or!(self.table(target, 4, 0)?, return Ok(None));
+        // The entry that first made each sum, out of the ones before it
+        let mut via = vec![UNREACHED; table.width];
+        via[table.origin] = EMPTY;
+        for (i, &value) in table.values.iter().enumerate() {
+            for (from, to) in table.steps(value) {
+                if via[from] != UNREACHED && via[to] == UNREACHED {
+                    via[to] = i as u32;
+                }
+            }
+        }
+        if via[table.goal] == UNREACHED {
+            return Ok(None);
+        }
+
+        let mut chosen = Vec::new();
+        let mut pos = table.goal;
+        while via[pos] != EMPTY {
+            let i = via[pos] as usize;
+            chosen.push(i);
+            pos = table.back(pos, i);
+        }
+        Ok(Some(self.report.select(chosen.into_iter())))
+    }
+
+    /// Finds a subset of the entries that sums to `target` with as few
+    /// entries as possible
+    ///
+    /// This needs a bit for every sum for every entry on top of what
+    /// [`find`](Self::find) needs, to work out which entries were used.
+    pub fn smallest(&self, target: T) -> Result<Option<Selection<T>>, SubsetSumError> {
+        let table = unwrap_or!(self.table(target, 4, 1)?, return Ok(None));
+        let mut fewest = vec![u32::MAX; table.width];
+        fewest[table.origin] = 0;
+        // Whether each entry made each sum with fewer entries than before it
+        let mut taken = vec![0u64; (table.values.len() * table.width).div_ceil(64)];
+        for (i, &value) in table.values.iter().enumerate() {
+            for (from, to) in table.steps(value) {
+                if fewest[from] != u32::MAX && fewest[from] + 1 < fewest[to] {
+                    fewest[to] = fewest[from] + 1;
+                    let bit = i * table.width + to;
+                    taken[bit / 64] |= 1 << (bit % 64);
+                }
+            }
+        }
+        if fewest[table.goal] == u32::MAX {
+            return Ok(None);
+        }
+
+        let mut chosen = Vec::new();
+        let mut pos = table.goal;
+        for i in (0..table.values.len()).rev() {
+            let bit = i * table.width + pos;
+            if taken[bit / 64] & (1 << (bit % 64)) != 0 {
+                chosen.push(i);
+                pos = table.back(pos, i);
+            }
+        }
+        Ok(Some(self.report.select(chosen.into_iter())))
+    }
+
+    /// Counts the subsets of the entries that sum to `target`
+    pub fn count(&self, target: T) -> Result<u64, SubsetSumError> {
+        let table = unwrap_or!(self.table(target, 8, 0)?, return Ok(0));
+        // These saturate, and anything that adds to a saturated count is too
+        let mut counts = vec![0u64; table.width];
+        counts[table.origin] = 1;
+        for &value in &table.values {
+            for (from, to) in table.steps(value) {
+                counts[to] = counts[to].saturating_add(counts[from]);
+            }
+        }
+        match counts[table.goal] {
+            u64::MAX => Err(SubsetSumError::TooMany),
+            count => Ok(count),
+        }
+    }
+
+    /// Works out the sums a table needs to cover on the way to `target`, and
+    /// checks a table with `bytes` for each sum and `bits` for each sum for
+    /// each entry, along with the widened entries, fits in the memory limit
+    ///
+    /// Returns `None` if no subset can reach `target`.
+    fn table(&self, target: T, bytes: u128, bits: u128) -> Result<Option<SumTable>, SubsetSumError> {
+        let entries = &self.report.0;
+        let too_big = SubsetSumError::TooBig { needed: u128::MAX, limit: self.memory_limit };
+        let sum = |negative: bool| entries.iter()
+            .map(|value| value.to_i128())
+            .filter(|&value| value != 0 && (value < 0) == negative)
+            .try_fold(0i128, |sum, value| sum.checked_add(value))
+            .ok_or_else(|| too_big.clone());
+        let (smallest, biggest) = (sum(true)?, sum(false)?);
+
+        // Without entries going the other way, sums past the target never come back
+        let target = target.to_i128();
+        let lo = if biggest == 0 { smallest.max(target) } else { smallest };
+        let hi = if smallest == 0 { biggest.min(target) } else { biggest };
+        if !(lo..=hi).contains(&target) {
+            return Ok(None);
+        }
+
+        let width = hi.checked_sub(lo).and_then(|width| width.checked_add(1)).ok_or(too_big)? as u128;
+        let needed = width.saturating_mul(bytes)
+            .saturating_add(width.saturating_mul(entries.len() as u128).saturating_mul(bits) / 8)
+            .saturating_add((entries.len() * mem::size_of::<i128>()) as u128);
+        if needed > self.memory_limit as u128 || entries.len() >= EMPTY as usize {
+            return Err(SubsetSumError::TooBig { needed, limit: self.memory_limit });
+        }
+        let values = entries.iter().map(|value| value.to_i128()).collect_vec();
+        Ok(Some(SumTable {
+            width: width as usize,
+            origin: (-lo) as usize,
+            goal: (target - lo) as usize,
+            values,
+        }))
+    }
+}
+
+/// A table with a place for each sum from some lowest one up, with the
+/// entries widened to work through it
+struct SumTable {
+    values: Vec<i128>,
+    width: usize,
+    /// Where the sum zero is
+    origin: usize,
+    /// Where the target sum is
+    goal: usize,
+}
+
+impl SumTable {
+    /// The pairs of places an entry worth `value` goes from and to, ordered
+    /// so that no sum it makes is added to again
+    fn steps(&self, value: i128) -> impl Iterator<Item = (usize, usize)> {
+        let width = self.width;
+        let shift = usize::try_from(value.unsigned_abs()).unwrap_or(usize::MAX).min(width);
+        if value > 0 {
+            Either::Left((0..width - shift).rev().map(move |from| (from, from + shift)))
+        } else {
+            Either::Right((shift..width).map(move |from| (from, from - shift)))
+        }
+    }
+
+    /// The place that taking out entry `i` from the sum at `pos` goes to
+    fn back(&self, pos: usize, i: usize) -> usize {
+        (pos as i128 - self.values[i]) as usize
+    }
+}
+
+impl<T: Amount> From<Vec<T>> for ExpenseReport<T> {
+    fn from(entries: Vec<T>) -> Self {
+        Self::new(entries)
+    }
+}
+
+impl<T: Amount> FromStr for ExpenseReport<T> {
+    type Err = ParseError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        s.parse_lines()
-            .map(ExpenseReport)
-            .map_err(|_| "couldn't parse number")
+        s.parse_lines().map(Self::new)
     }
 }
 
@@ -60,7 +796,7 @@ impl FromStr for ExpenseReport {
 mod tests {
     use super::*;
 
-    static TEST_INPUT: &'static str = "\
+    static TEST_INPUT: &str = "\
 1721
 979
 366
@@ -74,11 +810,265 @@ mod tests {
 
     #[test]
     fn test_part1() {
-        assert_eq!(product_of_2_entries_with_sum_2020(&test_entries()), Ok(514579));
+        assert_eq!(product_of_entries_with_sum(&test_entries(), 2, TARGET).unwrap(), 514579);
     }
 
     #[test]
     fn test_part2() {
-        assert_eq!(product_of_3_entries_with_sum_2020(&test_entries()), Ok(241861950));
+        assert_eq!(product_of_entries_with_sum(&test_entries(), 3, TARGET).unwrap(), 241861950);
+    }
+
+    #[test]
+    fn test_find_sum() {
+        let report = test_entries();
+        let values = |k, target| report.find_sum(k, target).unwrap().map(|sum| sum.values);
+        assert_eq!(values(2, 2020), Some(vec![1721, 299]));
+        assert_eq!(values(3, 2020), Some(vec![979, 366, 675]));
+        assert_eq!(values(1, 979), Some(vec![979]));
+        assert_eq!(values(4, 299 + 366 + 675 + 1721), Some(vec![1721, 366, 299, 675]));
+        assert_eq!(values(6, 5496), Some(vec![1721, 979, 366, 299, 675, 1456]));
+        assert_eq!(values(0, 0), Some(vec![]));
+        assert_eq!(values(2, 2021), None);
+        assert_eq!(values(7, 5496), None);
+        assert_eq!(report.find_sum(3, 2020).unwrap().unwrap().indices, vec![1, 2, 4]);
+        assert_eq!(ExpenseReport(vec![-5, 3, 10]).find_sum(2, 5).unwrap().unwrap().values, vec![-5, 10]);
+    }
+
+    #[test]
+    fn test_find_sum_distinct() {
+        // 1010 + 1010 isn't a sum unless 1010 is on two lines
+        let report: ExpenseReport = "1010\n500\n20".parse().unwrap();
+        assert_eq!(report.find_sum(2, 2020).unwrap(), None);
+        assert!(Day1::part1(&report).is_err());
+        let report: ExpenseReport = "1010\n500\n1010".parse().unwrap();
+        assert_eq!(
+            report.find_sum(2, 2020).unwrap(),
+            Some(Selection { indices: vec![0, 2], values: vec![1010, 1010] })
+        );
+
+        // Nor can a pair be reused for the third entry
+        let report: ExpenseReport = "1000\n20\n1500".parse().unwrap();
+        assert_eq!(report.find_sum(3, 2020).unwrap(), None);
+        assert_eq!(report.find_sum(3, 2520).unwrap().unwrap().indices, vec![0, 1, 2]);
+        let report: ExpenseReport = "7\n7\n7\n2006".parse().unwrap();
+        assert_eq!(report.find_sum(3, 2020).unwrap().unwrap().indices, vec![0, 1, 3]);
+        assert_eq!(report.find_sum(4, 2027).unwrap().unwrap().indices, vec![0, 1, 2, 3]);
+        assert_eq!(report.find_sum(4, 2034).unwrap(), None);
+    }
+
+    #[test]
+    fn test_sums() {
+        let report: ExpenseReport = "1000\n1020\n10\n1010\n1010\n1000\n20".parse().unwrap();
+        let indices = |k, target| report.sums(k, target).map(|sum| sum.unwrap().indices).collect_vec();
+        assert_eq!(indices(2, 2020), vec![vec![0, 1], vec![1, 5], vec![3, 4]]);
+        assert_eq!(
+            indices(3, 2020),
+            vec![vec![0, 2, 3], vec![0, 2, 4], vec![2, 3, 5], vec![2, 4, 5], vec![0, 5, 6]]
+        );
+        assert_eq!(indices(1, 1010), vec![vec![3], vec![4]]);
+        assert_eq!(indices(0, 0), vec![Vec::<usize>::new()]);
+        assert!(indices(2, 5).is_empty());
+        assert!(indices(8, 5070).is_empty());
+        assert_eq!(indices(7, 5070).len(), 1);
+
+        for (k, target) in [(0, 0), (1, 1010), (2, 2020), (3, 2020), (3, 2030), (4, 3040), (2, 1)].iter() {
+            assert_eq!(report.count_sums(*k, *target).unwrap(), report.sums(*k, *target).count() as u64);
+        }
+        assert_eq!(test_entries().count_sums(2, 2020).unwrap(), 1);
+        assert_eq!(test_entries().count_sums(3, 2020).unwrap(), 1);
+
+        // Checked against trying every combination
+        let mut rng = Rng::new(3);
+        for _ in 0..20 {
+            let entries = (0..12).map(|_| rng.range(-5..20) as i32).collect_vec();
+            let report = ExpenseReport(entries.clone());
+            for k in 0..7 {
+                let target = rng.range(-5..40) as i32;
+                let expected = (0..entries.len()).combinations(k)
+                    .filter(|indices| indices.iter().map(|&i| entries[i]).sum::<i32>() == target)
+                    .collect_vec();
+                let found = report.sums(k, target).map(|sum| sum.unwrap().indices).sorted().collect_vec();
+                assert_eq!(found, expected, "{:?} k={} target={}", entries, k, target);
+                assert_eq!(report.count_sums(k, target).unwrap(), expected.len() as u64);
+            }
+        }
+    }
+
+    #[test]
+    fn test_overflow() {
+        let input = "2000000\n1000\n3000";
+        let report: ExpenseReport = input.parse().unwrap();
+        let e = product_of_entries_with_sum(&report, 3, 2_004_000).unwrap_err();
+        let overflow = Overflow { operation: Operation::Product, values: vec![2_000_000, 1000, 3000] };
+        assert_eq!(e.downcast_ref(), Some(&overflow));
+        assert_eq!(e.to_string(), "2000000 * 1000 * 3000 overflows i32");
+
+        let report: ExpenseReport<i64> = input.parse().unwrap();
+        assert_eq!(product_of_entries_with_sum(&report, 3, 2_004_000).unwrap(), 6_000_000_000_000);
+        let report: ExpenseReport<u64> = input.parse().unwrap();
+        assert_eq!(product_of_entries_with_sum(&report, 3, 2_004_000).unwrap(), 6_000_000_000_000);
+
+        // Sums go past the limits of the entries' type part way through
+        let report = ExpenseReport(vec![i32::MAX, 1, -1, i32::MIN]);
+        assert_eq!(report.count_sums(2, i32::MAX).unwrap(), 0);
+        assert_eq!(report.count_sums(2, i32::MIN).unwrap(), 0);
+        assert_eq!(report.find_sum(2, -1).unwrap().unwrap().indices, vec![0, 3]);
+        assert_eq!(report.find_sum(3, 0).unwrap().unwrap().indices, vec![0, 1, 3]);
+        assert_eq!(report.find_sum(4, -1).unwrap().unwrap().indices, vec![0, 1, 2, 3]);
+        let report = ExpenseReport(vec![i32::MIN, -1, 1]);
+        assert_eq!(report.find_sum(3, i32::MIN).unwrap().unwrap().indices, vec![0, 1, 2]);
+        assert_eq!(report.count_sums(3, i32::MIN).unwrap(), 1);
+        let report = ExpenseReport(vec![u64::MAX, 1, u64::MAX - 1]);
+        assert_eq!(report.count_sums(2, u64::MAX).unwrap(), 1);
+        assert_eq!(report.find_sum(3, 0).unwrap(), None);
+
+        // Only i128 sums can overflow, which is an error rather than a missed sum
+        let report = ExpenseReport(vec![i128::MIN, -1, 1]);
+        let overflow = Overflow { operation: Operation::Sum, values: vec![i128::MIN, -1] };
+        assert_eq!(report.find_sum(3, i128::MIN), Err(overflow.clone()));
+        assert_eq!(report.count_sums(3, i128::MIN), Err(overflow));
+        assert_eq!(report.sums(3, i128::MIN).count(), 1);
+        assert_eq!(report.find_sum(2, i128::MIN + 1).unwrap().unwrap().indices, vec![0, 2]);
+        let e = product_of_entries_with_sum(&report, 3, i128::MIN).unwrap_err();
+        assert_eq!(e.to_string(), format!("{} + -1 overflows i128", i128::MIN));
+    }
+
+    #[test]
+    fn test_widths() {
+        let input = format!("{}\n5\n{}", i128::MIN, i128::MAX);
+        let report: ExpenseReport<i128> = input.parse().unwrap();
+        let sum = report.find_sum(2, -1).unwrap().unwrap();
+        assert_eq!(sum.indices, vec![0, 2]);
+        assert_eq!(sum.product(), Err(Overflow { operation: Operation::Product, values: vec![i128::MIN, i128::MAX] }));
+        assert_eq!(report.find_sum(2, i128::MAX - 5).unwrap(), None);
+        let sum = report.find_sum(2, i128::MIN + 5).unwrap().unwrap();
+        assert_eq!(sum.product(), Err(Overflow { operation: Operation::Product, values: vec![i128::MIN, 5] }));
+
+        let big = 1 << 40;
+        let report = ExpenseReport::<i64>::new(vec![big, 3, big + 7, 2020 - 3]);
+        assert_eq!(report.find_sum(2, 2 * big + 7).unwrap().unwrap().values, vec![big, big + 7]);
+        assert_eq!(product_of_entries_with_sum(&report, 2, TARGET.into()).unwrap(), 3 * 2017);
+    }
+
+    #[test]
+    fn test_from_vec() {
+        let report: ExpenseReport<u64> = vec![1721, 979, 366, 299, 675, 1456].into();
+        assert_eq!(report.entries(), test_entries().entries().iter().map(|&n| n as u64).collect_vec().as_slice());
+        assert_eq!(report.find_sum(3, 2020).unwrap().unwrap().product().unwrap(), 241861950);
+        assert_eq!(report.count_sums(2, 2020).unwrap(), 1);
+        assert_eq!(report.subset_sums(DEFAULT_MEMORY_LIMIT).count(2020).unwrap(), 2);
+    }
+
+    #[test]
+    fn test_subset_sums() {
+        let report = test_entries();
+        let sums = report.subset_sums(DEFAULT_MEMORY_LIMIT);
+        let subset = sums.find(2020).unwrap().unwrap();
+        assert_eq!(subset.values.iter().sum::<i32>(), 2020);
+        assert_eq!(sums.smallest(2020).unwrap().unwrap().indices, vec![0, 3]);
+        assert_eq!(sums.smallest(1721 + 979 + 366).unwrap().unwrap().values, vec![1721, 979, 366]);
+        assert_eq!(sums.count(2020), Ok(2));
+        assert_eq!(sums.find(0), Ok(Some(Selection { indices: vec![], values: vec![] })));
+        assert_eq!(sums.find(5), Ok(None));
+        assert_eq!(sums.find(-1), Ok(None));
+        assert_eq!(sums.count(5496), Ok(1));
+
+        let report = ExpenseReport(vec![5, 5, 5, 5, 20, 1, 14]);
+        let sums = report.subset_sums(DEFAULT_MEMORY_LIMIT);
+        assert_eq!(sums.smallest(20).unwrap().unwrap().indices, vec![4]);
+        assert_eq!(sums.smallest(25).unwrap().unwrap().values, vec![5, 20]);
+        assert_eq!(sums.smallest(15).unwrap().unwrap().values, vec![1, 14]);
+        assert_eq!(sums.smallest(16).unwrap().unwrap().values, vec![5, 5, 5, 1]);
+        assert_eq!(sums.count(20), Ok(1 + 1 + 4));
+
+        // Checked against trying every subset
+        let mut rng = Rng::new(5);
+        for _ in 0..50 {
+            let entries = (0..10).map(|_| rng.range(-8..15)).collect_vec();
+            let report = ExpenseReport(entries.clone());
+            let sums = report.subset_sums(DEFAULT_MEMORY_LIMIT);
+            let mut by_sum = (0..1 << entries.len())
+                .map(|mask: u32| (0..entries.len()).filter(|&i| mask & 1 << i != 0).collect_vec())
+                .map(|indices| (indices.iter().map(|&i| entries[i]).sum::<i64>(), indices))
+                .into_group_map();
+            for target in -20..40 {
+                let subsets = by_sum.remove(&target).unwrap_or_default();
+                let found = sums.find(target).unwrap();
+                assert_eq!(found.is_some(), !subsets.is_empty(), "{:?} {}", entries, target);
+                if let Some(found) = found {
+                    assert!(subsets.contains(&found.indices));
+                    let smallest = sums.smallest(target).unwrap().unwrap();
+                    assert!(subsets.contains(&smallest.indices));
+                    assert_eq!(smallest.indices.len(), subsets.iter().map(Vec::len).min().unwrap());
+                }
+                assert_eq!(sums.count(target), Ok(subsets.len() as u64), "{:?} {}", entries, target);
+            }
+        }
+    }
+
+    #[test]
+    fn test_subset_sum_limits() {
+        let report = ExpenseReport(vec![u64::MAX / 4, 3, u64::MAX / 4]);
+        let sums = report.subset_sums(DEFAULT_MEMORY_LIMIT);
+        assert_eq!(sums.find(u64::MAX / 2 - 1), Err(SubsetSumError::TooBig {
+            needed: u128::from(u64::MAX / 2) * 4 + 3 * 16,
+            limit: DEFAULT_MEMORY_LIMIT,
+        }));
+        // Targets nothing can reach don't need a table
+        assert_eq!(sums.count(u64::MAX), Ok(0));
+
+        // A count for each sum up to 4000, plus the 3 entries widened to i128
+        let report = ExpenseReport(vec![1000, 2000, 3000]);
+        let needed = 4 * 4001 + 3 * 16;
+        assert_eq!(report.subset_sums(needed).find(4000).unwrap().unwrap().values, vec![1000, 3000]);
+        let e = report.subset_sums(needed - 1).find(4000).unwrap_err();
+        assert_eq!(e.to_string(), "a table of subset sums would need 16052 bytes, over the limit of 16051");
+        // Finding the smallest subset needs more memory
+        assert!(report.subset_sums(needed).smallest(4000).is_err());
+        assert!(report.subset_sums(needed + 3 * 4001 / 8).smallest(4000).is_ok());
+        assert!(report.subset_sums(needed + 3 * 4001 / 8 - 1).smallest(4000).is_err());
+
+        let report = ExpenseReport(vec![0i32; 70]);
+        assert_eq!(report.subset_sums(DEFAULT_MEMORY_LIMIT).count(0), Err(SubsetSumError::TooMany));
+        let report = ExpenseReport(vec![0i32; 63]);
+        assert_eq!(report.subset_sums(DEFAULT_MEMORY_LIMIT).count(0), Ok(1 << 63));
+    }
+
+    #[test]
+    fn test_find_sum_large() {
+        // Big enough that trying every 4 entries would never finish
+        let mut rng = Rng::new(1);
+        let mut entries = (0..20_000).map(|_| rng.range(1_000..1_000_000) as i32).collect_vec();
+        entries.extend(&[123, 456, 789, 999]);
+        rng.shuffle(&mut entries);
+        let report = ExpenseReport(entries);
+        let sum = report.find_sum(4, 123 + 456 + 789 + 999).unwrap().unwrap();
+        assert_eq!(sum.values.iter().sorted().collect_vec(), vec![&123, &456, &789, &999]);
+        assert!(sum.indices.iter().all(|&i| report.entries()[i] < 1_000));
+        assert_eq!(report.find_sum(4, 1_000).unwrap(), None);
+        let sum = report.find_sum(3, 2_000_000).unwrap().unwrap();
+        assert_eq!(sum.values.iter().sum::<i32>(), 2_000_000);
+        assert_eq!(sum.indices.iter().unique().count(), 3);
+        assert_eq!(report.count_sums(4, 123 + 456 + 789 + 999).unwrap(), 1);
+
+        // Nothing to prune here, since the sums just come out even
+        let entries = (0..2_000).map(|_| 2 * rng.range(500..500_000) as i32).collect_vec();
+        let report = ExpenseReport(entries);
+        assert_eq!(report.find_sum(4, 1_000_001).unwrap(), None);
+        assert_eq!(report.count_sums(4, 1_000_001).unwrap(), 0);
+        let sum = report.find_sum(4, 1_000_000).unwrap().unwrap();
+        assert_eq!(sum.values.iter().sum::<i32>(), 1_000_000);
+        assert_eq!(sum.indices.iter().unique().count(), 4);
+    }
+
+    #[test]
+    fn test_generate() {
+        for seed in 0..5 {
+            let input = Day1::generate(&mut Rng::new(seed), 1000);
+            let report = Day1::parse(&input).unwrap();
+            assert_eq!(report.entries().len(), 1000);
+            assert_eq!(report.count_sums(2, TARGET).unwrap(), 1);
+            assert_eq!(report.count_sums(3, TARGET).unwrap(), 1);
+        }
     }
 }
@@ -1,163 +1,166 @@
 use std::{fmt::Debug, str::FromStr};
-use anyhow::{Context, Error};
+use anyhow::{Context, Result};
 use itertools::Itertools;
 use crate::util::*;
 
-pub(crate) fn day2() {
-    let input = get_input("day2");
+pub struct Day2;
 
-    part1(&input);
-    part2(&input);
-}
+impl Solution for Day2 {
+    type Input<'a> = PasswordDatabase;
+    type Answer = usize;
 
-fn part1(input: &str) {
-    let answer = count_valid_passwords::<old_job::Policy>(
-        &input.parse_lines().unwrap()
-    );
+    fn parse(input: &str) -> Result<PasswordDatabase, ParseError> {
+        Ok(PasswordDatabase {
+            old_job: input.parse_lines()?,
+            toboggan: input.parse_lines()?,
+        })
+    }
 
-    println!("According to their policies, {} passwords are valid", answer);
-    assert_eq!(answer, 469);
-}
+    fn part1(db: &PasswordDatabase) -> Result<usize> {
+        count_valid_passwords(&db.old_job)
+    }
 
-fn part2(input: &str) {
-    let answer = count_valid_passwords::<north_pole_toboggan_rental_shop::Policy>(
-        &input.parse_lines().unwrap()
-    );
+    fn part2(db: &PasswordDatabase) -> Result<usize> {
+        count_valid_passwords(&db.toboggan)
+    }
+
+    fn generate(rng: &mut Rng, size: usize) -> String {
+        static LETTERS: &[u8] = b"abcdefghijklmnopqrstuvwxyz";
+        (0..size.max(1)).map(|_| {
+            let letter = *rng.choose(LETTERS) as char;
+            let len = rng.range(2..21);
+            let passwd: String = (0..len)
+                .map(|_| if rng.chance(0.3) { letter } else { *rng.choose(LETTERS) as char })
+                .collect();
+            // Both numbers have to be positions in the password for the new policy
+            let lo = rng.range(1..len);
+            let hi = rng.range(lo + 1..len + 1);
+            format!("{}-{} {}: {}", lo, hi, letter, passwd)
+        }).join("\n")
+    }
+}
 
-    println!("According to their policies, {} passwords are valid", answer);
-    assert_eq!(answer, 267);
+/// The password list, interpreted according to each policy
+pub struct PasswordDatabase {
+    pub old_job: Vec<PasswordWithPolicy<old_job::Policy>>,
+    pub toboggan: Vec<PasswordWithPolicy<north_pole_toboggan_rental_shop::Policy>>,
 }
 
 #[derive(Debug)]
-struct PasswordWithPolicy<P> {
+pub struct PasswordWithPolicy<P> {
     policy: P,
     passwd: Box<str>,
 }
 
 impl<P: PasswordPolicy> PasswordWithPolicy<P> {
-    fn is_valid(&self) -> bool {
+    pub fn policy(&self) -> &P {
+        &self.policy
+    }
+
+    pub fn passwd(&self) -> &str {
+        &self.passwd
+    }
+
+    pub fn is_valid(&self) -> Result<bool> {
         self.policy.validate(&self.passwd)
     }
 }
 
-impl<P: FromStr<Err = Error>> FromStr for PasswordWithPolicy<P> {
-    type Err = Error;
+impl<P: FromStr<Err = ParseError>> FromStr for PasswordWithPolicy<P> {
+    type Err = ParseError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        const SEP: &'static str = ": ";
-        const ERR: &str = "invalid str";
-
-        let sep_idx = s.find(SEP).context(ERR)?;
-
-        let policy = s[..sep_idx].parse()?;
-        let passwd = s[sep_idx+SEP.len()..].into();
-
-        Ok(Self { policy, passwd })
+        Parser::parse(s, |p| {
+            let policy_str = p.until(": ")?;
+            let policy = policy_str.parse().map_err(|e: ParseError| e.within(s, policy_str))?;
+            p.literal(": ")?;
+            let passwd = p.rest().into();
+            Ok(Self { policy, passwd })
+        })
     }
 }
 
-trait PasswordPolicy: Debug {
-    fn validate(&self, passwd: &str) -> bool;
+pub trait PasswordPolicy: Debug {
+    /// Checks `passwd` against this policy, or fails if the policy can't be
+    /// applied to it
+    fn validate(&self, passwd: &str) -> Result<bool>;
 }
 
-fn count_valid_passwords<P: PasswordPolicy>(
-    passwords: &Vec<PasswordWithPolicy<P>>
-) -> usize {
-    passwords.iter().filter(|pp| pp.is_valid()).count()
+pub fn count_valid_passwords<P: PasswordPolicy>(
+    passwords: &[PasswordWithPolicy<P>]
+) -> Result<usize> {
+    passwords.iter().try_fold(0, |count, pp| Ok(count + pp.is_valid()? as usize))
 }
 
-mod north_pole_toboggan_rental_shop {
+pub mod north_pole_toboggan_rental_shop {
     use super::*;
 
     #[derive(Copy, Clone, Debug)]
-    pub(super) struct Policy {
+    pub struct Policy {
         letter: char,
         positions: [usize; 2],
     }
 
     impl PasswordPolicy for Policy {
-        fn validate(&self, passwd: &str) -> bool {
+        fn validate(&self, passwd: &str) -> Result<bool> {
             let Self { letter, positions } = *self;
-            positions.iter().copied()
-                .filter(|pos| letter == passwd.chars().nth(pos - 1)
-                    .expect("position not found in password"))
-                .count() == 1
+            let matches: Vec<bool> = positions.iter().copied()
+                .map(|pos| pos.checked_sub(1)
+                    .and_then(|i| passwd.chars().nth(i))
+                    .map(|c| c == letter)
+                    .with_context(|| format!("position {} isn't in password '{}'", pos, passwd)))
+                .try_collect()?;
+            Ok(matches.iter().filter(|&&matched| matched).count() == 1)
         }
     }
 
     impl FromStr for Policy {
-        type Err = Error;
+        type Err = ParseError;
 
         fn from_str(s: &str) -> Result<Self, Self::Err> {
-            const HYPHEN: &str = "-";
-            const SPACE: &str = " ";
-            const ERR: &str = "invalid policy";
-
-            (|| -> anyhow::Result<Self> {
-                let hyphen_idx = s.find(HYPHEN)
-                    .context("no hyphen")?;
-                let space_idx = s.find(SPACE)
-                    .context("no space")?;
-
-                let pos1 = s[..hyphen_idx].parse()
-                    .context("invalid 1st position")?;
-                let pos2 = s[hyphen_idx+HYPHEN.len()..space_idx].parse()
-                    .context("invalid 2nd position")?;
-                let letter = s[space_idx+SPACE.len()..].chars().exactly_one()
-                    .map_err(error_from_debug)
-                    .context("expected a single char")?;
-
+            Parser::parse(s, |p| {
+                let pos1 = p.int()?;
+                p.literal("-")?;
+                let pos2 = p.int()?;
+                p.literal(" ")?;
+                let letter = p.char()?;
                 Ok(Self { positions: [pos1, pos2], letter })
-            })()
-            .context(ERR)
+            })
         }
     }
 }
 
-mod old_job {
+pub mod old_job {
     use super::*;
 
     #[derive(Copy, Clone, Debug)]
-    pub(super) struct Policy {
+    pub struct Policy {
         letter: char,
         min: usize,
         max: usize,
     }
 
     impl PasswordPolicy for Policy {
-        fn validate(&self, passwd: &str) -> bool {
+        fn validate(&self, passwd: &str) -> Result<bool> {
             let Self { min, max, letter } = *self;
-            (min..=max).contains(
+            Ok((min..=max).contains(
                 &passwd.chars().filter(|c| *c == letter).count()
-            )
+            ))
         }
     }
 
     impl FromStr for Policy {
-        type Err = Error;
+        type Err = ParseError;
 
         fn from_str(s: &str) -> Result<Self, Self::Err> {
-            const HYPHEN: &str = "-";
-            const SPACE: &str = " ";
-            const ERR: &str = "invalid policy";
-
-            (|| -> anyhow::Result<Self> {
-                let hyphen_idx = s.find(HYPHEN)
-                    .context("no hyphen")?;
-                let space_idx = s.find(SPACE)
-                    .context("no space")?;
-
-                let min = s[..hyphen_idx].parse()
-                    .context("invalid min")?;
-                let max = s[hyphen_idx+HYPHEN.len()..space_idx].parse()
-                    .context("invalid max")?;
-                let letter = s[space_idx+SPACE.len()..].chars().exactly_one()
-                    .map_err(|e| Error::msg(format!("{:?}", e)))
-                    .context("expected a single char")?;
-
+            Parser::parse(s, |p| {
+                let min = p.int()?;
+                p.literal("-")?;
+                let max = p.int()?;
+                p.literal(" ")?;
+                let letter = p.char()?;
                 Ok(Self { min, max, letter })
-            })()
-            .context(ERR)
+            })
         }
     }
 }
@@ -166,17 +169,21 @@ mod old_job {
 mod tests {
     use super::*;
 
-    static TEST_INPUT: &'static str = "\
+    static TEST_INPUT: &str = "\
 1-3 a: abcde
 1-3 b: cdefg
 2-9 c: ccccccccc";
 
+    fn test_passwords<P: FromStr<Err = ParseError>>() -> Vec<PasswordWithPolicy<P>> {
+        TEST_INPUT.parse_lines().unwrap()
+    }
+
     #[test]
     fn test_part1() {
         assert_eq!(
             count_valid_passwords::<old_job::Policy>(
-                &TEST_INPUT.parse_lines().unwrap()
-            ),
+                &test_passwords()
+            ).unwrap(),
             2
         );
     }
@@ -185,9 +192,42 @@ mod tests {
     fn test_part2() {
         assert_eq!(
             count_valid_passwords::<north_pole_toboggan_rental_shop::Policy>(
-                &TEST_INPUT.parse_lines().unwrap()
-            ),
+                &test_passwords()
+            ).unwrap(),
             1
         );
     }
+
+    #[test]
+    fn test_parse_error_location() {
+        let parsed: Result<Vec<PasswordWithPolicy<old_job::Policy>>, _> =
+            "1-3 a: abcde\n1-x b: cdefg".parse_lines();
+        let e = parsed.unwrap_err();
+        assert_eq!((e.line, e.column(), e.len), (2, 3, 1));
+        assert_eq!(e.message, "expected a number");
+    }
+
+    #[test]
+    fn test_positions_out_of_range() {
+        for input in &["1-4 a: abc", "0-2 a: abc"] {
+            let db = Day2::parse(input).unwrap();
+            assert_eq!(Day2::part1(&db).unwrap(), 1);
+            let e = Day2::part2(&db).unwrap_err();
+            assert!(e.to_string().ends_with("isn't in password 'abc'"), "{}", e);
+        }
+    }
+
+    #[test]
+    fn test_generate() {
+        for seed in 0..5 {
+            let input = Day2::generate(&mut Rng::new(seed), 1000);
+            let db = Day2::parse(&input).unwrap();
+            assert_eq!(db.old_job.len(), 1000);
+            // Both policies should pass some passwords and fail others, and
+            // part 2 fails if a position isn't in the password
+            for &valid in &[Day2::part1(&db).unwrap(), Day2::part2(&db).unwrap()] {
+                assert!(0 < valid && valid < 1000, "seed {}: {} valid", seed, valid);
+            }
+        }
+    }
 }
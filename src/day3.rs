@@ -1,28 +1,40 @@
-use std::{iter::FromIterator, convert::{TryFrom, TryInto}, ops::Index, str::FromStr};
-use anyhow::{Context, Error, Result};
+use std::{
+    convert::{TryFrom, TryInto},
+    fmt::{Display, Formatter, Result as FmtResult},
+    str::FromStr,
+};
+use anyhow::{Error, Result};
 use itertools::Itertools;
 use crate::util::*;
 
-pub(crate) fn day3() {
-    let map: Map = get_input("day3").parse().unwrap();
-    part1(&map);
-    part2(&map);
-}
+pub struct Day3;
 
-fn part1(map: &Map) {
-    let trees = map.count_trees_in_slope(3, 1);
+impl Solution for Day3 {
+    type Input<'a> = Map;
+    type Answer = usize;
 
-    println!("A slope of right 3 and down 1 would encounter {} trees", trees);
-    assert_eq!(trees, 259);
-}
+    fn parse(input: &str) -> Result<Map, ParseError> {
+        input.parse()
+    }
+
+    fn part1(map: &Map) -> Result<usize> {
+        Ok(map.count_trees_in_slope(3, 1))
+    }
 
-fn part2(map: &Map) {
-    let product: usize = PART2_SLOPES.iter()
-        .map(|&(right, down)| map.count_trees_in_slope(right, down))
-        .product();
+    fn part2(map: &Map) -> Result<usize> {
+        Ok(PART2_SLOPES.iter()
+            .map(|&(right, down)| map.count_trees_in_slope(right, down))
+            .product())
+    }
 
-    println!("The product of the trees encountered on each slope is {}", product);
-    assert_eq!(product, 2224913600);
+    fn generate(rng: &mut Rng, size: usize) -> String {
+        const WIDTH: usize = 31;
+        (0..size.max(1))
+            .map(|_| (0..WIDTH)
+                .map(|_| if rng.chance(0.2) { '#' } else { '.' })
+                .collect::<String>())
+            .join("\n")
+    }
 }
 
 static PART2_SLOPES: &[(usize, usize)] = &[
@@ -33,53 +45,31 @@ static PART2_SLOPES: &[(usize, usize)] = &[
     (1, 2),
 ];
 
-struct SquareRows(Box<[Square]>);
-
-impl SquareRows {
-    fn wrap_index(&self, index: usize) -> usize {
-        index % self.0.len()
-    }
-}
-
-impl Index<usize> for SquareRows {
-    type Output = Square;
-
-    fn index(&self, index: usize) -> &Self::Output {
-        &self.0[self.wrap_index(index)]
-    }
-}
+/// The map of trees, which repeats to the right forever
+pub struct Map(Grid<Square>);
 
-impl FromIterator<Square> for SquareRows {
-    fn from_iter<T: IntoIterator<Item = Square>>(iter: T) -> Self {
-        Self(iter.into_iter().collect())
+impl Map {
+    pub fn grid(&self) -> &Grid<Square> {
+        &self.0
     }
-}
 
-struct Map(Box<[SquareRows]>);
-
-impl Map {
-    fn count_trees_in_slope(&self, right: usize, down: usize) -> usize {
-        self.0.iter().step_by(down).enumerate()
-            .filter(|(i, row)| row[right * i] == Square::Tree)
+    pub fn count_trees_in_slope(&self, right: usize, down: usize) -> usize {
+        (0..self.0.height()).step_by(down).enumerate()
+            .filter(|&(i, y)| self.0[(right * i, y)] == Square::Tree)
             .count()
     }
 }
 
 impl FromStr for Map {
-    type Err = Error;
+    type Err = ParseError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        s.lines()
-            .map(|line| line.chars()
-                .map(Square::try_from)
-                .collect::<Result<_>>())
-            .collect::<Result<_>>()
-            .map(Map)
+        s.parse().map(|grid: Grid<_>| Map(grid.with_edges(Edges::Wrapping)))
     }
 }
 
 #[derive(Copy, Clone, Debug, PartialEq)]
-enum Square { Open, Tree }
+pub enum Square { Open, Tree }
 
 impl TryFrom<char> for Square {
     type Error = Error;
@@ -88,19 +78,28 @@ impl TryFrom<char> for Square {
         Ok(match c {
             '.' => Self::Open,
             '#' => Self::Tree,
-            _ => return Err(Error::msg("unknown square"))
+            _ => return Err(Error::msg(format!("unknown square {:?}", c)))
         })
     }
 }
 
+impl Display for Square {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        match self {
+            Self::Open => write!(f, "."),
+            Self::Tree => write!(f, "#"),
+        }
+    }
+}
+
 impl FromStr for Square {
-    type Err = Error;
+    type Err = ParseError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        s.chars().exactly_one()
-            .map_err(error_from_debug)
-            .context("squares are only one char")?
+        s.chars().exactly_one().ok()
+            .parse_context(s, s, "squares are only one char")?
             .try_into()
+            .map_err(|e| ParseError::new(s, s, e))
     }
 }
 
@@ -136,4 +135,20 @@ mod tests {
             .product();
         assert_eq!(product, 336);
     }
+
+    #[test]
+    fn test_display_round_trips() {
+        let map: Map = TEST_INPUT.parse().unwrap();
+        assert_eq!(map.grid().to_string(), TEST_INPUT);
+    }
+
+    #[test]
+    fn test_generate() {
+        for seed in 0..5 {
+            let input = Day3::generate(&mut Rng::new(seed), 100);
+            let map = Day3::parse(&input).unwrap();
+            assert_eq!((map.grid().width(), map.grid().height()), (31, 100));
+            assert!(Day3::part1(&map).unwrap() > 0);
+        }
+    }
 }
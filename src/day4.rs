@@ -1,50 +1,84 @@
 use std::{collections::HashMap, convert::{TryFrom, TryInto}, ops::RangeInclusive};
-use anyhow::{Context, Error, Result, bail};
+use anyhow::{Context, Result, bail};
 use itertools::Itertools;
 use crate::util::*;
 
-pub(crate) fn day4() {
-    let input = get_input("day4");
-    let passports = input.as_str().try_into().unwrap();
-    part1(&passports);
-    part2(&passports);
-}
+pub struct Day4;
 
-fn part1(passports: &Passports) {
-    let valid = passports.0.iter()
-        .filter(|p| p.is_valid_part1())
-        .count();
+impl Solution for Day4 {
+    type Input<'a> = Passports<'a>;
+    type Answer = usize;
 
-    println!("{} passports are valid", valid);
-    assert_eq!(valid, 260);
-}
+    fn parse(input: &str) -> Result<Passports<'_>, ParseError> {
+        input.try_into()
+    }
+
+    fn part1(passports: &Passports<'_>) -> Result<usize> {
+        Ok(passports.0.iter()
+            .filter(|p| p.is_valid_part1())
+            .count())
+    }
 
-fn part2(passports: &Passports) {
-    let valid = passports.0.iter()
-        .filter(|p| p.is_valid_part2())
-        .count();
+    fn part2(passports: &Passports<'_>) -> Result<usize> {
+        Ok(passports.0.iter()
+            .filter(|p| p.is_valid_part2())
+            .count())
+    }
 
-    println!("{} passports are valid", valid);
-    assert_eq!(valid, 153);
+    fn generate(rng: &mut Rng, size: usize) -> String {
+        (0..size.max(1)).map(|_| {
+            let mut fields = Vec::new();
+            for &field in Passport::REQUIRED_FIELDS.iter().chain(&["cid"]) {
+                if rng.chance(0.9) {
+                    let value = if rng.chance(0.8) {
+                        Passport::generate_valid(rng, field)
+                    } else {
+                        Passport::generate_invalid(rng, field)
+                    };
+                    fields.push(format!("{}:{}", field, value));
+                }
+            }
+            rng.shuffle(&mut fields);
+
+            let mut record = String::new();
+            for (i, field) in fields.iter().enumerate() {
+                if i > 0 {
+                    record.push(if rng.chance(0.3) { '\n' } else { ' ' });
+                }
+                record.push_str(field);
+            }
+            record
+        }).join("\n\n")
+    }
 }
 
-struct Passports<'a>(Box<[Passport<'a>]>);
+pub struct Passports<'a>(Box<[Passport<'a>]>);
+
+impl<'a> Passports<'a> {
+    pub fn passports(&self) -> &[Passport<'a>] {
+        &self.0
+    }
+}
 
 impl<'a> TryFrom<&'a str> for Passports<'a> {
-    type Error = Error;
+    type Error = ParseError;
 
     fn try_from(s: &'a str) -> Result<Self, Self::Error> {
         s
-            .split("\n\n")
-            .map(Passport::try_from)
+            .records()
+            .map(|(_, record)| Passport::try_from(record).map_err(|e| e.within(s, record)))
             .try_collect()
             .map(Passports)
     }
 }
 
-struct Passport<'a>(HashMap<&'a str, &'a str>);
+pub struct Passport<'a>(HashMap<&'a str, &'a str>);
 
 impl<'a> Passport<'a> {
+    pub fn get(&self, field: &str) -> Option<&'a str> {
+        self.0.get(field).copied()
+    }
+
     const REQUIRED_FIELDS: &'static [&'static str] = &[
         "byr",
         "iyr",
@@ -56,12 +90,12 @@ impl<'a> Passport<'a> {
         // "cid",
     ];
 
-    fn is_valid_part1(&self) -> bool {
+    pub fn is_valid_part1(&self) -> bool {
         Self::REQUIRED_FIELDS.iter()
             .all(|field| self.0.contains_key(field))
     }
 
-    fn is_valid_part2(&self) -> bool {
+    pub fn is_valid_part2(&self) -> bool {
         if !self.is_valid_part1() { return false; }
 
         self.validate().is_ok()
@@ -69,7 +103,7 @@ impl<'a> Passport<'a> {
 
     // I added error messages to help diagnose a bug...but in the process I
     // fixed the bug anyway ¯\_(ツ)_/¯
-    fn validate(&self) -> Result<()> {
+    pub fn validate(&self) -> Result<()> {
         if !self.is_valid_part1() {
             bail!("missing field(s)");
         }
@@ -122,19 +156,46 @@ impl<'a> Passport<'a> {
 
         Ok(())
     }
+
+    fn generate_valid(rng: &mut Rng, field: &str) -> String {
+        match field {
+            "byr" => rng.range(1920..2003).to_string(),
+            "iyr" => rng.range(2010..2021).to_string(),
+            "eyr" => rng.range(2020..2031).to_string(),
+            "hgt" if rng.chance(0.5) => format!("{}cm", rng.range(150..194)),
+            "hgt" => format!("{}in", rng.range(59..77)),
+            "hcl" => format!("#{:06x}", rng.range(0..0x100_0000)),
+            "ecl" => rng.choose(&["amb", "blu", "brn", "gry", "grn", "hzl", "oth"]).to_string(),
+            "pid" => format!("{:09}", rng.range(0..1_000_000_000)),
+            _ => rng.range(100..350).to_string(),
+        }
+    }
+
+    fn generate_invalid(rng: &mut Rng, field: &str) -> String {
+        match field {
+            "byr" => rng.range(2003..2030).to_string(),
+            "iyr" => rng.range(1990..2010).to_string(),
+            "eyr" => rng.range(1960..2020).to_string(),
+            "hgt" => rng.range(50..250).to_string(),
+            "hcl" => format!("{:06x}", rng.range(0..0x100_0000)),
+            "ecl" => rng.choose(&["zzz", "gmt", "xry", "utc"]).to_string(),
+            "pid" if rng.chance(0.5) => rng.range(0..100_000_000).to_string(),
+            "pid" => rng.range(1_000_000_000..1_000_000_000_000).to_string(),
+            _ => Self::generate_valid(rng, field),
+        }
+    }
 }
 
 impl<'a> TryFrom<&'a str> for Passport<'a> {
-    type Error = Error;
+    type Error = ParseError;
 
     fn try_from(s: &'a str) -> Result<Self, Self::Error> {
         s
-            .split(&[' ', '\n'][..])
-            .filter(|kv| kv.len() > 0)
+            .split_whitespace()
             .map(|kv| kv
                 .split(':')
                 .collect_tuple::<(&str, &str)>()
-                .with_context(|| format!("invalid key:value pair '{}'", kv)))
+                .parse_context(s, kv, "invalid key:value pair"))
             .try_collect()
             .map(Passport)
     }
@@ -212,4 +273,47 @@ iyr:2010 hgt:158cm hcl:#b6652a ecl:blu byr:1944 eyr:2021 pid:093154719";
             }
         }
     }
+
+    #[test]
+    fn test_crlf_and_blank_lines() {
+        static INPUT: &str = "\
+ecl:gry pid:860033327 eyr:2020 hcl:#fffffd\r
+byr:1937 iyr:2017 cid:147 hgt:183cm\r
+  \r
+\r
+hcl:#cfa07d eyr:2025 pid:166559648\r
+iyr:2011 ecl:brn hgt:59in\r
+\r
+";
+        let passports: Passports = INPUT.try_into().unwrap();
+        assert_eq!(passports.0.len(), 2);
+        assert_eq!(passports.0[0].get("hgt"), Some("183cm"));
+        assert!(passports.0[0].is_valid_part2());
+        assert!(!passports.0[1].is_valid_part1());
+    }
+
+    #[test]
+    fn test_generate() {
+        let input = Day4::generate(&mut Rng::new(0), 100);
+        assert_eq!(Day4::parse(&input).unwrap().passports().len(), 100);
+
+        // A passport with every field valid but one is invalid
+        let mut rng = Rng::new(0);
+        let record = |fields: &[(&str, String)]| {
+            fields.iter().map(|(field, value)| format!("{}:{}", field, value)).join(" ")
+        };
+        for _ in 0..1000 {
+            let valid: Vec<(&str, String)> = Passport::REQUIRED_FIELDS.iter()
+                .map(|&field| (field, Passport::generate_valid(&mut rng, field)))
+                .collect();
+            let s = record(&valid);
+            assert!(Passport::try_from(s.as_str()).unwrap().is_valid_part2(), "{}", s);
+            for i in 0..valid.len() {
+                let mut invalid = valid.clone();
+                invalid[i].1 = Passport::generate_invalid(&mut rng, invalid[i].0);
+                let s = record(&invalid);
+                assert!(!Passport::try_from(s.as_str()).unwrap().is_valid_part2(), "{}", s);
+            }
+        }
+    }
 }
@@ -1,54 +1,80 @@
-use anyhow::Context;
+use anyhow::{Context, Result};
 use itertools::Itertools;
 use crate::util::*;
 
-pub(crate) fn day5() {
-    let mut seat_ids = get_input("day5").lines().map(|line| {
-        let (row, col) = row_col_from_binary_str(line)
-            .with_context(|| format!("couldn't parse pass '{}'", line))
-            .unwrap();
-        seat_id(row, col)
-    }).collect_vec();
-    seat_ids.sort();
-
-    part1(&seat_ids);
-    part2(&seat_ids);
-}
-
-fn part1(seat_ids: &Vec<u64>) {
-    let highest_seat_id = seat_ids.iter().copied().max()
-        .expect("no passes");
+pub struct Day5;
+
+impl Solution for Day5 {
+    /// The seat IDs of every boarding pass, sorted
+    type Input<'a> = Vec<u64>;
+    type Answer = u64;
+
+    fn parse(input: &str) -> Result<Vec<u64>, ParseError> {
+        let mut seat_ids: Vec<_> = input.lines().map(|line| {
+            row_col_from_binary_str(line)
+                .map(|(row, col)| seat_id(row, col))
+                .parse_context(input, line, "couldn't parse boarding pass")
+        }).try_collect()?;
+        seat_ids.sort_unstable();
+        Ok(seat_ids)
+    }
 
-    println!("The highest seat ID is {}", highest_seat_id);
-    assert_eq!(highest_seat_id, 864);
-}
+    fn part1(seat_ids: &Vec<u64>) -> Result<u64> {
+        seat_ids.iter().copied().max()
+            .context("no passes")
+    }
 
-fn part2(seat_ids: &Vec<u64>) {
-    let seat_id = seat_ids.iter().copied().tuple_windows()
-        .find(|&(prev, next)| prev + 1 != next)
-        .map(|(prev, _)| prev + 1)
-        .expect("couldn't find your seat ID");
+    fn part2(seat_ids: &Vec<u64>) -> Result<u64> {
+        seat_ids.iter().copied().tuple_windows()
+            .find(|&(prev, next)| prev + 1 != next)
+            .map(|(prev, _)| prev + 1)
+            .context("couldn't find your seat ID")
+    }
 
-    println!("The ID of your seat is {}", seat_id);
-    assert_eq!(seat_id, 739);
+    fn generate(rng: &mut Rng, size: usize) -> String {
+        // A contiguous run of seats with yours missing somewhere in the middle
+        let count = size.clamp(2, 1022) as i64;
+        let first = rng.range(1..1024 - count);
+        let yours = first + rng.range(1..count);
+        let mut seat_ids: Vec<u64> = (first..=first + count)
+            .filter(|&id| id != yours)
+            .map(|id| id as u64)
+            .collect();
+        rng.shuffle(&mut seat_ids);
+        seat_ids.iter().map(|&id| binary_str_from_seat_id(id)).join("\n")
+    }
 }
 
-fn seat_id(row: u8, col: u8) -> u64 {
+pub fn seat_id(row: u8, col: u8) -> u64 {
     let row: u64 = row.into();
     let col: u64 = col.into();
     row * 8 + col
 }
 
-fn row_col_from_binary_str(s: &str) -> Option<(u8, u8)> {
+pub fn binary_str_from_seat_id(seat_id: u64) -> String {
+    (0..10).rev()
+        .map(|bit| {
+            let set = seat_id & (1 << bit) != 0;
+            match (bit >= 3, set) {
+                (true, false) => 'F',
+                (true, true) => 'B',
+                (false, false) => 'L',
+                (false, true) => 'R',
+            }
+        })
+        .collect()
+}
+
+pub fn row_col_from_binary_str(s: &str) -> Option<(u8, u8)> {
     const ROW_LEN: usize = 7;
     const COL_LEN: usize = 3;
 
-    if !(s.len() == (ROW_LEN + COL_LEN) && s.chars().all(|c| c.is_ascii())) {
+    if !(s.len() == (ROW_LEN + COL_LEN) && s.is_ascii()) {
         return None;
     }
 
-    let row_bin = s[..ROW_LEN].as_bytes();
-    let col_bin = s[ROW_LEN..].as_bytes();
+    let row_bin = &s.as_bytes()[..ROW_LEN];
+    let col_bin = &s.as_bytes()[ROW_LEN..];
     if col_bin.len() != COL_LEN {
         return None;
     }
@@ -121,4 +147,15 @@ mod tests {
             assert_eq!(expected_pass, &Pass { s, row, col, seat_id });
         }
     }
+
+    #[test]
+    fn test_generate() {
+        for (seed, &size) in (0..5).zip(&[2, 10, 100, 1000, 1022]) {
+            let input = Day5::generate(&mut Rng::new(seed), size);
+            let seat_ids = Day5::parse(&input).unwrap();
+            assert_eq!(seat_ids.len(), size);
+            let gaps = seat_ids.iter().tuple_windows().filter(|&(prev, next)| prev + 1 != *next).count();
+            assert_eq!(gaps, 1, "seed {}", seed);
+        }
+    }
 }
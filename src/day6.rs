@@ -1,102 +1,95 @@
 use std::{str::FromStr, collections::HashSet};
-use anyhow::{Error, Result, bail};
+use anyhow::Result;
 use itertools::Itertools;
 use crate::util::*;
 
-pub(crate) fn day6() {
-    let all_group_answers: AllGroupsAnswers = get_input("day6").parse().unwrap();
-    part1(&all_group_answers);
-    part2(&all_group_answers);
-}
+pub struct Day6;
 
-fn part1(all_group_answers: &AllGroupsAnswers) {
-    let sum_of_unique_yeses = all_group_answers.count_yeses_any();
+impl Solution for Day6 {
+    type Input<'a> = AllGroupsAnswers;
+    type Answer = usize;
 
-    println!(
-        "The sum of the number of questions to which anyone answered yes in each group is {}",
-        sum_of_unique_yeses
-    );
-    assert_eq!(sum_of_unique_yeses, 6662);
-}
+    fn parse(input: &str) -> Result<AllGroupsAnswers, ParseError> {
+        input.parse()
+    }
+
+    fn part1(all_group_answers: &AllGroupsAnswers) -> Result<usize> {
+        Ok(all_group_answers.count_yeses_any())
+    }
 
-fn part2(all_group_answers: &AllGroupsAnswers) {
-    let sum_of_yeses_all = all_group_answers.count_yeses_all();
+    fn part2(all_group_answers: &AllGroupsAnswers) -> Result<usize> {
+        Ok(all_group_answers.count_yeses_all())
+    }
 
-    println!(
-        "The sum of the number of questions to which everyone answered yes in each group is {}",
-        sum_of_yeses_all
-    );
-    assert_eq!(sum_of_yeses_all, 3382);
+    fn generate(rng: &mut Rng, size: usize) -> String {
+        (0..size.max(1)).map(|_| {
+            let people = rng.range(1..6);
+            (0..people).map(|_| {
+                let mut questions: Vec<char> = ('a'..='z').collect();
+                rng.shuffle(&mut questions);
+                let yeses = rng.index(questions.len()) + 1;
+                questions[..yeses].iter().collect::<String>()
+            }).join("\n")
+        }).join("\n\n")
+    }
 }
 
-struct AllGroupsAnswers(Vec<GroupAnswers>);
+pub struct AllGroupsAnswers(Vec<GroupAnswers>);
 
 impl AllGroupsAnswers {
-    fn count_yeses_any(&self) -> usize {
+    pub fn count_yeses_any(&self) -> usize {
         self.0.iter().map(|g| g.count_yeses_any()).sum()
     }
 
-    fn count_yeses_all(&self) -> usize {
+    pub fn count_yeses_all(&self) -> usize {
         self.0.iter().map(|g| g.count_yeses_all()).sum()
     }
 }
 
 impl FromStr for AllGroupsAnswers {
-    type Err = Error;
+    type Err = ParseError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        s.split("\n\n")
-            .map(str::parse)
+        s.records()
+            .map(|(_, group)| group.parse().map_err(|e: ParseError| e.within(s, group)))
             .try_collect()
             .map(AllGroupsAnswers)
     }
 }
 
-struct GroupAnswers {
+pub struct GroupAnswers {
     any: HashSet<char>,
     all: HashSet<char>,
 }
 
 impl GroupAnswers {
-    fn count_yeses_any(&self) -> usize {
-        self.any.iter().count()
+    pub fn count_yeses_any(&self) -> usize {
+        self.any.len()
     }
 
-    fn count_yeses_all(&self) -> usize {
-        self.all.iter().count()
+    pub fn count_yeses_all(&self) -> usize {
+        self.all.len()
     }
 }
 
 impl FromStr for GroupAnswers {
-    type Err = Error;
+    type Err = ParseError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        fn letter_or_err(c: char) -> Result<char> {
-            if !c.is_ascii_alphabetic() { bail!("non-letter found") }
-            Ok(c)
-        }
-
-        fn all_letters_or_err(s: &str) -> Result<&str> {
-            s.chars()
-                .map(letter_or_err)
-                // I don't want the chars
-                .map_results(|_| ())
-                .try_collect()
-                .map(|()| s)
+        if let Some((i, c)) = s.char_indices()
+            .find(|&(_, c)| !c.is_whitespace() && !c.is_ascii_alphabetic())
+        {
+            return Err(ParseError::new(s, &s[i..i + c.len_utf8()], "non-letter found"));
         }
 
         let any = s.chars()
-            .filter(|&c| c != '\n')
-            .map(letter_or_err)
-            .try_collect()?;
+            .filter(|c| !c.is_whitespace())
+            .collect();
 
         let all = s.lines()
-            .map(all_letters_or_err)
-            .map_results(|line| line.chars().collect::<HashSet<char>>())
-            .fold1(|set, line| Ok(
-                set?.intersection(&line?).copied().collect()
-            ))
-            .transpose()?
+            .map(str::trim)
+            .map(|line| line.chars().collect::<HashSet<char>>())
+            .fold1(|set, line| set.intersection(&line).copied().collect())
             .unwrap_or_default();
 
         Ok(Self { any, all })
@@ -141,4 +134,23 @@ b";
             .count_yeses_all();
         assert_eq!(sum_of_yeses_all, 6);
     }
+
+    #[test]
+    fn test_crlf_and_blank_lines() {
+        let input = INPUT.replace('\n', "\r\n").replace("\r\n\r\n", "\r\n \r\n\r\n") + "\r\n\r\n";
+        let answers: AllGroupsAnswers = input.parse().unwrap();
+        assert_eq!(answers.count_yeses_any(), 11);
+        assert_eq!(answers.count_yeses_all(), 6);
+    }
+
+    #[test]
+    fn test_generate() {
+        for seed in 0..5 {
+            let input = Day6::generate(&mut Rng::new(seed), 100);
+            let groups = Day6::parse(&input).unwrap();
+            assert_eq!(groups.0.len(), 100);
+            assert!(groups.0.iter().all(|group| group.count_yeses_any() > 0));
+            assert!(Day6::part2(&groups).unwrap() < Day6::part1(&groups).unwrap());
+        }
+    }
 }
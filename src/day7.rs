@@ -4,108 +4,192 @@ use std::{
     num::NonZeroUsize,
     str::FromStr,
 };
-use anyhow::{Context, Error, Result};
+use anyhow::{Context, Result, bail};
 use itertools::Itertools;
 use crate::util::*;
 
-pub(crate) fn day7() {
-    let rules = get_input("day7").parse().unwrap();
-    part1(&rules);
-    part2(&rules);
-}
+pub struct Day7;
 
-fn part1(rules: &RuleSet) {
-    let count = rules.count_bags_that_can_contain(&BagDesc::new("shiny gold"));
+impl Solution for Day7 {
+    type Input<'a> = RuleSet;
+    type Answer = usize;
 
-    println!("{} bag colors can eventually contain at least one shiny gold bag", count);
-    assert_eq!(count, 278);
-}
+    fn parse(input: &str) -> Result<RuleSet, ParseError> {
+        input.parse()
+    }
 
-fn part2(rules: &RuleSet) {
-    let count = rules.count_required_bags_for(&BagDesc::new("shiny gold"));
+    fn part1(rules: &RuleSet) -> Result<usize> {
+        rules.count_bags_that_can_contain(&BagDesc::new("shiny gold"))
+    }
 
-    println!("{} individual bags are required inside your single shiny gold bag", count);
-    assert_eq!(count, 45157);
+    fn part2(rules: &RuleSet) -> Result<usize> {
+        rules.count_required_bags_for(&BagDesc::new("shiny gold"))
+    }
+
+    fn generate(rng: &mut Rng, size: usize) -> String {
+        static ADJECTIVES: &[&str] = &[
+            "light", "dark", "bright", "muted", "faded", "dotted", "vibrant", "dull",
+            "pale", "drab", "wavy", "posh", "clear", "striped", "plaid", "mirrored",
+        ];
+        static COLOURS: &[&str] = &[
+            "red", "orange", "white", "yellow", "gold", "olive", "plum", "blue",
+            "black", "green", "teal", "lime", "tan", "salmon", "crimson", "violet",
+        ];
+        // Bags only contain bags from the next layer down, so there are no cycles
+        // and the number of required bags stays small
+        const LAYERS: usize = 5;
+
+        let count = size.max(LAYERS);
+        let mut names: Vec<String> = COLOURS.iter()
+            .cartesian_product(ADJECTIVES)
+            .map(|(colour, adjective)| format!("{} {}", adjective, colour))
+            .collect();
+        rng.shuffle(&mut names);
+        let mut names: Vec<String> = (0..count)
+            .map(|i| {
+                let name = &names[i % names.len()];
+                match i / names.len() {
+                    0 => name.clone(),
+                    n => name.replacen(' ', &format!("{} ", n + 1), 1),
+                }
+            })
+            .filter(|name| name != "shiny gold")
+            .collect();
+        names.insert(count / 3, "shiny gold".to_string());
+        names.truncate(count);
+
+        let layer_of = |i: usize| i * LAYERS / count;
+        let mut lines: Vec<String> = (0..count).map(|i| {
+            let next_layer: Vec<usize> = (i + 1..count)
+                .filter(|&j| layer_of(j) == layer_of(i) + 1)
+                .collect();
+            let mut contains = Vec::new();
+            if !next_layer.is_empty() {
+                for _ in 0..rng.range(0..5) {
+                    let j = *rng.choose(&next_layer);
+                    if contains.iter().all(|bdc: &BagDescCount| bdc.bag.as_str() != names[j]) {
+                        contains.push(BagDescCount {
+                            count: NonZeroUsize::new(rng.range(1..6) as usize).unwrap(),
+                            bag: BagDesc(names[j].as_str().into()),
+                        });
+                    }
+                }
+            }
+            let rule = Rule { contains: contains.into() };
+            format!("{} bags contain {}.", names[i], rule)
+        }).collect();
+        rng.shuffle(&mut lines);
+        lines.join("\n")
+    }
 }
 
-struct RuleSet(HashMap<BagDesc, Rule>);
+pub struct RuleSet(HashMap<BagDesc, Rule>);
 
 impl RuleSet {
-    fn count_bags_that_can_contain(&self, bag: &BagDesc) -> usize {
-        fn can_contain(map: &HashMap<BagDesc, Rule>, needle: &BagDesc, haystack: &BagDesc) -> bool {
+    pub fn get(&self, bag: &BagDesc) -> Option<&Rule> {
+        self.0.get(bag)
+    }
+
+    /// Counts the bags that can contain `bag`, or fails if any bag it has to
+    /// look inside can contain itself
+    pub fn count_bags_that_can_contain(&self, bag: &BagDesc) -> Result<usize> {
+        /// Whether `haystack` can contain `needle`, recursively, remembering
+        /// the answers in `known`; `None` there is a bag still being looked in
+        fn can_contain<'a>(
+            map: &'a HashMap<BagDesc, Rule>,
+            needle: &BagDesc,
+            haystack: &'a BagDesc,
+            known: &mut HashMap<&'a BagDesc, Option<bool>>,
+        ) -> Result<bool> {
             assert_ne!(needle, haystack);
 
+            match known.get(haystack) {
+                Some(Some(answer)) => return Ok(*answer),
+                Some(None) => bail!("{} bags can contain themselves", haystack),
+                None => {}
+            }
             let rule = if let Some(rule) = map.get(haystack) { rule }
-                else { return false; };
-
-            rule.contains.iter().any(|BagDescCount { bag: haystack, .. }|
-                haystack == needle || can_contain(map, needle, haystack))
+                else { return Ok(false); };
+
+            known.insert(haystack, None);
+            let mut answer = false;
+            for BagDescCount { bag: haystack, .. } in rule.contains.iter() {
+                if haystack == needle || can_contain(map, needle, haystack, known)? {
+                    answer = true;
+                    break;
+                }
+            }
+            known.insert(haystack, Some(answer));
+            Ok(answer)
         }
 
         let map = &self.0;
+        let mut known = HashMap::new();
 
         map.keys()
-            .filter(|&top_level_bag|
-                // Skip the bag we're searching for, ofc
-                top_level_bag != bag &&
-                // Check if this bag can contain the bag we're searching for
-                can_contain(map, bag, top_level_bag))
-            .count()
-    }
-
-    /// Counts the bags required to be contained inside `bag`
-    fn count_required_bags_for(&self, bag: &BagDesc) -> usize {
-        /// Counts the required bags for `bag`, recursively
-        fn count_contained_bags(
-            rules: &HashMap<BagDesc, Rule>, bag: &BagDesc
-        ) -> usize {
-            let rule = unwrap_or!(rules.get(bag), return 0);
-
-            rule.contains.iter()
-                .map(|BagDescCount { bag, count }| {
-                    let count = count.get();
-
-                    let contained_bags = count_contained_bags(rules, bag);
-
-                    // There are `count` bags of this kind, so there are `count * contained_bags`
-                    // in them, so there are `count + count * contained_bags` bags total, or,
-                    // `count * (1 + contained_bags)`.
-                    count * (1 + contained_bags)
-                })
-                .sum()
+            // Skip the bag we're searching for, ofc
+            .filter(|&top_level_bag| top_level_bag != bag)
+            // Check if this bag can contain the bag we're searching for
+            .map(|top_level_bag| can_contain(map, bag, top_level_bag, &mut known))
+            .fold_results(0, |count, can| count + can as usize)
+    }
+
+    /// Counts the bags required to be contained inside `bag`, or fails if one
+    /// of them has to contain itself
+    pub fn count_required_bags_for(&self, bag: &BagDesc) -> Result<usize> {
+        /// Counts the required bags for `bag`, recursively, remembering the
+        /// counts in `counts`; `None` there is a bag still being counted
+        fn count_contained_bags<'a>(
+            rules: &'a HashMap<BagDesc, Rule>,
+            bag: &'a BagDesc,
+            counts: &mut HashMap<&'a BagDesc, Option<usize>>,
+        ) -> Result<usize> {
+            match counts.get(bag) {
+                Some(Some(count)) => return Ok(*count),
+                Some(None) => bail!("{} bags have to contain themselves", bag),
+                None => {}
+            }
+            let rule = unwrap_or!(rules.get(bag), return Ok(0));
+
+            counts.insert(bag, None);
+            let mut total = 0usize;
+            for BagDescCount { bag, count } in rule.contains.iter() {
+                let count = count.get();
+
+                let contained_bags = count_contained_bags(rules, bag, counts)?;
+
+                // There are `count` bags of this kind, so there are `count * contained_bags`
+                // in them, so there are `count + count * contained_bags` bags total, or,
+                // `count * (1 + contained_bags)`.
+                total = contained_bags.checked_add(1)
+                    .and_then(|bags| count.checked_mul(bags))
+                    .and_then(|bags| total.checked_add(bags))
+                    .context("too many bags to count")?;
+            }
+            counts.insert(bag, Some(total));
+            Ok(total)
         }
 
-        count_contained_bags(&self.0, bag)
+        count_contained_bags(&self.0, bag, &mut HashMap::new())
     }
 }
 
 impl FromStr for RuleSet {
-    type Err = Error;
-
-    fn from_str(s: &str) -> Result<Self> {
-        s.lines().map(|line| {
-            const CONTAIN: &str = "contain";
-
-            let contain_idx = line.find(CONTAIN)
-                .with_context(|| format!("failed to parse rule '{}'", line))?;
-
-            let outer_desc: BagDesc = line[..contain_idx].trim_end()
-                .parse()
-                .context("failed to parse rule")?;
-
-            let rule: Rule = line[(contain_idx+CONTAIN.len())..]
-                .trim_start()
-                .strip_suffix(".")
-                .with_context(|| format!("failed to parse '.' in rule '{}'", line))?
-                .parse()?;
-
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, ParseError> {
+        s.lines().map(|line| Parser::parse(line, |p| {
+            let outer_desc = BagDesc::parse_from(p)?;
+            p.literal(" contain ")?;
+            let rule = Rule::parse_from(p)?;
+            p.literal(".")?;
             Ok((outer_desc, rule))
-        }).try_collect().map(Self)
+        }).map_err(|e| e.within(s, line))).try_collect().map(Self)
     }
 }
 
-struct Rule {
-    contains: Box<[BagDescCount]>,
+pub struct Rule {
+    pub contains: Box<[BagDescCount]>,
 }
 
 impl Display for Rule {
@@ -122,27 +206,30 @@ impl Display for Rule {
     }
 }
 
-impl FromStr for Rule {
-    type Err = Error;
-
-    fn from_str(s: &str) -> Result<Self> {
-        let contains = if s == "no other bags" {
+impl Rule {
+    /// Parses the part of a rule after "contain", without the '.'
+    fn parse_from(p: &mut Parser<'_>) -> Result<Self, ParseError> {
+        let contains = if p.optional(|p| p.literal("no other bags")).is_some() {
             Default::default()
         } else {
-            s.split(",")
-                .map(str::trim_start)
-                .map(str::parse)
-                .try_collect()
-                .context("failed to parse rule")?
+            p.separated(", ", BagDescCount::parse_from)?.into()
         };
 
         Ok(Self { contains })
     }
 }
 
-struct BagDescCount {
-    count: NonZeroUsize,
-    bag: BagDesc,
+impl FromStr for Rule {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, ParseError> {
+        Parser::parse(s, Self::parse_from)
+    }
+}
+
+pub struct BagDescCount {
+    pub count: NonZeroUsize,
+    pub bag: BagDesc,
 }
 
 impl Display for BagDescCount {
@@ -155,33 +242,34 @@ impl Display for BagDescCount {
     }
 }
 
-impl FromStr for BagDescCount {
-    type Err = Error;
-
-    fn from_str(s: &str) -> Result<Self> {
-        let non_digit_idx = s
-            .find(|c: char| !c.is_ascii_digit())
-            .with_context(|| format!("found no number in '{}'", s))?;
-
-        let count = s[..non_digit_idx]
-            .parse()
-            .with_context(|| format!("couldn't parse number from '{}'", s))?;
+impl BagDescCount {
+    fn parse_from(p: &mut Parser<'_>) -> Result<Self, ParseError> {
+        let count = p.int()?;
+        p.literal(" ")?;
+        let bag = BagDesc::parse_from(p)?;
+        Ok(Self { count, bag })
+    }
+}
 
-        let bag_desc = s[non_digit_idx..].trim_start()
-            .parse()
-            .with_context(|| format!("couldn't parse bag description from '{}'", s))?;
+impl FromStr for BagDescCount {
+    type Err = ParseError;
 
-        Ok(Self { count, bag: bag_desc })
+    fn from_str(s: &str) -> Result<Self, ParseError> {
+        Parser::parse(s, Self::parse_from)
     }
 }
 
 #[derive(Clone, Debug, Hash, PartialEq, Eq)]
-struct BagDesc(Box<str>);
+pub struct BagDesc(Box<str>);
 
 impl BagDesc {
-    fn new(s: &'static str) -> Self {
+    pub fn new(s: &'static str) -> Self {
         Self(s.into())
     }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
 }
 
 impl Display for BagDesc {
@@ -190,18 +278,26 @@ impl Display for BagDesc {
     }
 }
 
-impl FromStr for BagDesc {
-    type Err = Error;
+impl BagDesc {
+    /// Parses the words of a description, then "bag" or "bags"
+    fn parse_from(p: &mut Parser<'_>) -> Result<Self, ParseError> {
+        let mut words = vec![p.word()?];
+        loop {
+            p.literal(" ")?;
+            match p.word()? {
+                "bag" | "bags" => break,
+                word => words.push(word),
+            }
+        }
+        Ok(Self(words.join(" ").into()))
+    }
+}
 
-    fn from_str(s: &str) -> Result<Self> {
-        let s = s.strip_suffix("bags")
-            .or_else(|| s.strip_suffix("bag"))
-            .with_context(|| format!(
-                "expected bag description to end with 'bag(s)' but it did not: '{}'", s
-            ))?
-            .trim_end();
+impl FromStr for BagDesc {
+    type Err = ParseError;
 
-        Ok(Self(s.into()))
+    fn from_str(s: &str) -> Result<Self, ParseError> {
+        Parser::parse(s, Self::parse_from)
     }
 }
 
@@ -223,14 +319,14 @@ dotted black bags contain no other bags.";
     #[test]
     fn test_part1() {
         let rules: RuleSet = INPUT.parse().unwrap();
-        let count = rules.count_bags_that_can_contain(&BagDesc::new("shiny gold"));
+        let count = rules.count_bags_that_can_contain(&BagDesc::new("shiny gold")).unwrap();
         assert_eq!(count, 4);
     }
 
     #[test]
     fn test_part2_ex1() {
         let rules: RuleSet = INPUT.parse().unwrap();
-        let count = rules.count_required_bags_for(&BagDesc::new("shiny gold"));
+        let count = rules.count_required_bags_for(&BagDesc::new("shiny gold")).unwrap();
         assert_eq!(count, 32);
     }
 
@@ -245,7 +341,46 @@ dark green bags contain 2 dark blue bags.
 dark blue bags contain 2 dark violet bags.
 dark violet bags contain no other bags.";
         let rules: RuleSet = INPUT.parse().unwrap();
-        let count = rules.count_required_bags_for(&BagDesc::new("shiny gold"));
+        let count = rules.count_required_bags_for(&BagDesc::new("shiny gold")).unwrap();
         assert_eq!(count, 126);
     }
+
+    #[test]
+    fn test_cycle() {
+        static INPUT: &str = "\
+light red bags contain 1 shiny gold bag.
+shiny gold bags contain 2 dark red bags.
+dark red bags contain 1 dark orange bag.
+dark orange bags contain 3 dark red bags.";
+        let rules: RuleSet = INPUT.parse().unwrap();
+        let e = rules.count_required_bags_for(&BagDesc::new("shiny gold")).unwrap_err();
+        assert!(e.to_string().ends_with("bags have to contain themselves"), "{}", e);
+        let e = rules.count_bags_that_can_contain(&BagDesc::new("light red")).unwrap_err();
+        assert!(e.to_string().ends_with("bags can contain themselves"), "{}", e);
+    }
+
+    #[test]
+    fn test_parse_error_location() {
+        static INPUT: &str = "\
+faded blue bags contain no other bags.
+shiny gold bags contain 1 dark olive bag, two vibrant plum bags.";
+        let e = INPUT.parse::<RuleSet>().err().unwrap();
+        assert_eq!((e.line, e.column()), (2, 43));
+        assert_eq!(e.message, "expected a number");
+    }
+
+    #[test]
+    fn test_generate() {
+        for seed in 0..5 {
+            let input = Day7::generate(&mut Rng::new(seed), 100);
+            let rules = Day7::parse(&input).unwrap();
+            assert_eq!(rules.0.len(), 100);
+            // Every bag has a rule, so none are left out of the counts
+            assert!(rules.get(&BagDesc::new("shiny gold")).is_some());
+            for rule in rules.0.values() {
+                assert!(rule.contains.iter().all(|BagDescCount { bag, .. }| rules.get(bag).is_some()));
+            }
+            Day7::part2(&rules).unwrap();
+        }
+    }
 }
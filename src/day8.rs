@@ -1,62 +1,111 @@
 use std::{collections::HashSet, convert::TryFrom, str::FromStr};
-use anyhow::{Context, Error, Result, bail};
+use anyhow::{Context, Result};
 use itertools::Itertools;
 use crate::util::*;
 
-pub(crate) fn day8() {
-    let program = get_input("day8").parse().unwrap();
-    part1(&program);
-    part2(&program);
-}
+pub struct Day8;
 
-fn part1(program: &Program) {
-    let acc = program.run_until_loop();
+impl Solution for Day8 {
+    type Input<'a> = Program;
+    type Answer = i32;
 
-    println!(
-        "The value in the accumulator, immediately before any instruction would be executed a second time, is {}",
-        acc
-    );
-    assert_eq!(acc, 1801);
-}
+    fn parse(input: &str) -> Result<Program, ParseError> {
+        input.parse()
+    }
+
+    fn part1(program: &Program) -> Result<i32> {
+        program.run_until_loop()
+    }
+
+    fn part2(program: &Program) -> Result<i32> {
+        program.run_fixing_loop()?
+            .context("no possible change of jmp->nop or nop->jmp can fix this program")
+    }
+
+    fn generate(rng: &mut Rng, size: usize) -> String {
+        // Start with a program that only jumps forward, so it always terminates.
+        // No jmp lands inside another's range and nops only point backwards, so
+        // flipping anything else on its path still runs into the loop made below.
+        let len = size.max(4);
+        let mut jmp_until = 0;
+        let mut instrs: Vec<Instr> = (0..len).map(|i| {
+            let max_jmp = (len - i).min(5) as i64;
+            let op = match rng.range(0..4) {
+                0 | 1 => Op::Acc,
+                2 if i > 0 && i >= jmp_until => Op::Jmp,
+                _ => Op::Nop,
+            };
+            let arg = match op {
+                Op::Acc => rng.range(-50..51),
+                Op::Jmp => rng.range(1..max_jmp + 1),
+                Op::Nop => rng.range(-(i as i64).min(20)..1),
+            };
+            if let Op::Jmp = op {
+                jmp_until = i + arg as usize;
+            }
+            Instr { op, arg: arg as i32 }
+        }).collect();
 
-fn part2(program: &Program) {
-    let acc = program.run_fixing_loop();
+        // Then break it by turning a nop on its path (or any instruction, if
+        // there are no nops on it) into a jmp back to an instruction it already ran
+        let mut path = vec![0];
+        while let Some(Instr { op, arg }) = instrs.get(*path.last().unwrap()) {
+            let i = *path.last().unwrap();
+            path.push(match op {
+                Op::Jmp => (i as i64 + i64::from(*arg)) as usize,
+                _ => i + 1,
+            });
+        }
+        path.pop();
+        let nops = (1..path.len()).filter(|&k| matches!(instrs[path[k]].op, Op::Nop)).collect_vec();
+        let k = if nops.is_empty() {
+            rng.range(1..path.len() as i64) as usize
+        } else {
+            *rng.choose(&nops)
+        };
+        let (target, broken) = (path[rng.index(k)], path[k]);
+        instrs[broken] = Instr { op: Op::Jmp, arg: target as i32 - broken as i32 };
 
-    println!(
-        "The value in the accumulator after the fixed program terminates is {}",
-        acc
-    );
-    assert_eq!(acc, 2060);
+        instrs.iter().map(|Instr { op, arg }| {
+            let op = match op { Op::Acc => "acc", Op::Jmp => "jmp", Op::Nop => "nop" };
+            format!("{} {:+}", op, arg)
+        }).join("\n")
+    }
 }
 
 #[derive(Clone, Debug)]
-struct Program {
+pub struct Program {
     instrs: Array<Instr>,
 }
 
 impl Program {
-    fn new(instrs: Array<Instr>) -> Self {
+    pub fn new(instrs: Array<Instr>) -> Self {
         Self { instrs }
     }
 
+    pub fn instrs(&self) -> &[Instr] {
+        &self.instrs
+    }
+
     /// Runs the program, but stops before an instruction would be executed twice.
     /// Returns the value of the accumulator at that point.
-    fn run_until_loop(&self) -> i32 {
-        match self.try_run_until_loop() {
+    pub fn run_until_loop(&self) -> Result<i32> {
+        self.try_run_until_loop().map(|run| match run {
             Ok(acc) => acc,
             Err(acc) => acc,
-        }
+        })
     }
 
     /// Runs the program, but stops if an instruction would be executed twice.
-    /// Returns the value of the accumulator at that point, in the Ok or Err.
-    fn try_run_until_loop(&self) -> Result<i32, i32> {
+    /// Returns the value of the accumulator at that point, in the inner Ok or
+    /// Err, or fails if it jumps to before the start of the program.
+    pub fn try_run_until_loop(&self) -> Result<Result<i32, i32>> {
         let mut acc = 0;
         let mut i = 0;
         let mut seen_indices = HashSet::new();
         while let Some(&Instr { op, arg }) = self.instrs.get(i) {
             if !seen_indices.insert(i) {
-                return Err(acc);
+                return Ok(Err(acc));
             }
 
             use Op::*;
@@ -67,7 +116,9 @@ impl Program {
                 Jmp => {
                     i = usize::try_from(
                         i64::try_from(i).unwrap() + i64::from(arg)
-                    ).expect("tried to jmp to before start of program");
+                    ).ok().with_context(|| format!(
+                        "instruction {} jumps to before the start of the program", i + 1
+                    ))?;
                     continue;
                 }
                 Nop => {}
@@ -75,14 +126,15 @@ impl Program {
 
             i += 1;
         }
-        Ok(acc)
+        Ok(Ok(acc))
     }
 
     /// Runs the program, trying to fix any one instruction that causes an infinite loop.
-    /// Returns the value of the accumulator after the program terminates successfully.
-    fn run_fixing_loop(&self) -> i32 {
-        if let Ok(acc) = self.try_run_until_loop() {
-            return acc;
+    /// Returns the value of the accumulator after the program terminates successfully,
+    /// or `None` if no single change fixes it.
+    pub fn run_fixing_loop(&self) -> Result<Option<i32>> {
+        if let Ok(acc) = self.try_run_until_loop()? {
+            return Ok(Some(acc));
         }
 
         let jmp_and_nop_indices = self.instrs.iter()
@@ -95,63 +147,72 @@ impl Program {
                 Op::Nop => Op::Jmp,
                 _ => unreachable!("we only included these ops")
             };
-            if let Ok(acc) = copy.try_run_until_loop() {
-                return acc;
+            // A change that jumps out of the start of the program doesn't fix it
+            if let Ok(Ok(acc)) = copy.try_run_until_loop() {
+                return Ok(Some(acc));
             }
         }
 
-        panic!("no possible change of jmp->nop or nop->jmp can fix this program");
+        Ok(None)
     }
 }
 
 impl FromStr for Program {
-    type Err = Error;
+    type Err = ParseError;
 
-    fn from_str(s: &str) -> Result<Self> {
+    fn from_str(s: &str) -> Result<Self, ParseError> {
         s.parse_lines().map(Self::new)
     }
 }
 
 #[derive(Copy, Clone, Debug)]
-struct Instr {
-    op: Op,
-    arg: i32,
+pub struct Instr {
+    pub op: Op,
+    pub arg: i32,
 }
 
 impl FromStr for Instr {
-    type Err = Error;
+    type Err = ParseError;
 
-    fn from_str(s: &str) -> Result<Self> {
-        let (op, arg) = s.splitn(2, ' ').collect_tuple()
-            .with_context(|| format!("expected 'op arg' but found {}", s))?;
-        let op = op.parse().context("failed to parse op")?;
-        let arg = arg.parse().context("failed to parse arg")?;
-        Ok(Self { op, arg })
+    fn from_str(s: &str) -> Result<Self, ParseError> {
+        Parser::parse(s, |p| {
+            let op = Op::parse_from(p)?;
+            p.literal(" ")?;
+            let arg = p.int()?;
+            Ok(Self { op, arg })
+        })
     }
 }
 
 #[derive(Copy, Clone, Debug)]
-enum Op {
+pub enum Op {
     Acc,
     Jmp,
     Nop,
 }
 
-impl FromStr for Op {
-    type Err = Error;
-
-    fn from_str(s: &str) -> Result<Self> {
+impl Op {
+    fn parse_from(p: &mut Parser<'_>) -> Result<Self, ParseError> {
         use Op::*;
 
-        Ok(match s {
+        let op = p.word()?;
+        Ok(match op {
             "acc" => Acc,
             "jmp" => Jmp,
             "nop" => Nop,
-            _ => bail!("unknown operation")
+            _ => return Err(p.error(op, "unknown operation")),
         })
     }
 }
 
+impl FromStr for Op {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, ParseError> {
+        Parser::parse(s, Self::parse_from)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -170,14 +231,51 @@ acc +6";
     #[test]
     fn test_part1() {
         let program: Program = INPUT.parse().unwrap();
-        let acc = program.run_until_loop();
+        let acc = program.run_until_loop().unwrap();
         assert_eq!(acc, 5);
     }
 
     #[test]
     fn test_part2() {
         let program: Program = INPUT.parse().unwrap();
-        let acc = program.run_fixing_loop();
-        assert_eq!(acc, 8);
+        let acc = program.run_fixing_loop().unwrap();
+        assert_eq!(acc, Some(8));
+    }
+
+    #[test]
+    fn test_jmp_before_start() {
+        let program: Program = "nop +0\njmp -2".parse().unwrap();
+        let e = program.run_until_loop().unwrap_err();
+        assert_eq!(e.to_string(), "instruction 2 jumps to before the start of the program");
+        assert!(program.run_fixing_loop().is_err());
+        // Flipping the nop would jump before the start, so it isn't a fix
+        let program: Program = "nop -1\njmp -1".parse().unwrap();
+        assert_eq!(program.run_fixing_loop().unwrap(), Some(0));
+    }
+
+    #[test]
+    fn test_parse_error_location() {
+        let e = "nop +0\nacc +1\njmp four".parse::<Program>().unwrap_err();
+        assert_eq!((e.line, e.column(), e.len), (3, 5, 4));
+    }
+
+    #[test]
+    fn test_generate() {
+        for seed in 0..40 {
+            let input = Day8::generate(&mut Rng::new(seed), 4 + 10 * seed as usize);
+            let program = Day8::parse(&input).unwrap();
+            assert!(program.try_run_until_loop().unwrap().is_err());
+            let fixes = (0..program.instrs().len()).filter(|&i| {
+                let mut copy = program.clone();
+                let op = &mut copy.instrs[i].op;
+                *op = match op {
+                    Op::Jmp => Op::Nop,
+                    Op::Nop => Op::Jmp,
+                    Op::Acc => return false,
+                };
+                matches!(copy.try_run_until_loop(), Ok(Ok(_)))
+            }).count();
+            assert_eq!(fixes, 1, "seed {}", seed);
+        }
     }
 }
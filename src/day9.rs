@@ -1,37 +1,88 @@
 use anyhow::{Context, Result};
+use itertools::Itertools;
 use crate::util::*;
 use self::util::*;
 
 /// The int size used in this challenge
 ///
 /// I overflowed with u32 lol, I didn't expect the numbers to be that big
-type Int = u64;
+pub type Int = u64;
 
-pub(crate) fn day9() {
-    let xmas_data = XmasData::new_parse(&get_input("day9"), 25).unwrap();
-    part1(&xmas_data);
-    part2(&xmas_data);
-}
+pub struct Day9;
 
-fn part1(xmas_data: &XmasData) {
-    let num = xmas_data.find_invalid_num().expect("no invalid number");
+/// The length of the preamble in the real input
+const PREAMBLE_LEN: usize = 25;
 
-    println!("The first number that does not have the property is {}", num);
-    assert_eq!(num, 731031916);
-}
+impl Solution for Day9 {
+    type Input<'a> = XmasData;
+    type Answer = Int;
+
+    fn parse(input: &str) -> Result<XmasData, ParseError> {
+        XmasData::new_parse(input, PREAMBLE_LEN)
+    }
 
-fn part2(xmas_data: &XmasData) {
-    let weakness = xmas_data.find_encryption_weakness().expect("no weakness");
+    fn part1(xmas_data: &XmasData) -> Result<Int> {
+        xmas_data.find_invalid_num().context("no invalid number")
+    }
 
-    println!(
-        "The encryption weakness in your XMAS-encrypted list of numbers is {}",
-        weakness
-    );
-    assert_eq!(weakness, 93396727);
+    fn part2(xmas_data: &XmasData) -> Result<Int> {
+        xmas_data.find_encryption_weakness().context("no weakness")
+    }
+
+    fn generate(rng: &mut Rng, size: usize) -> String {
+        fn is_pair_sum(window: &[Int], num: Int) -> bool {
+            window.iter().tuple_combinations().any(|(a, b)| a + b == num)
+        }
+
+        // Every number is at least the sum of the two smallest before it, so
+        // positive numbers double every PREAMBLE_LEN lines. Keeping two zeros in
+        // the window lets any number be carried forward as itself plus zero,
+        // which keeps them all small however long the input is.
+        const MAX: Int = 1_000_000;
+        let len = size.max(4 * PREAMBLE_LEN);
+        let mut data: Vec<Int> = (2..PREAMBLE_LEN)
+            .map(|_| rng.range(1..101) as Int)
+            .chain(vec![0, 0])
+            .collect();
+        rng.shuffle(&mut data);
+        let invalid_idx = rng.range(len as i64 / 2..len as i64) as usize;
+        let mut invalid_at = None;
+        while data.len() < len || invalid_at.is_none() {
+            let window = &data[data.len() - PREAMBLE_LEN..];
+            let last = data.len() - 1;
+            // Zeros that are still in the window 3 lines from now; the slack
+            // keeps a zero from landing right next to the invalid number, where
+            // the two would be a second run adding up to it.
+            let lasting_zeros = window[3..].iter().filter(|&&n| n == 0).count();
+            if lasting_zeros < 2 && invalid_at != Some(last) {
+                data.push(0);
+                continue;
+            }
+            if invalid_at.is_none() && data.len() >= invalid_idx && window[PREAMBLE_LEN - 1] != 0 {
+                // A contiguous run that isn't the sum of any 2 numbers in the window
+                let end = data.len() - rng.index(PREAMBLE_LEN);
+                let start = end - rng.range(2..6) as usize;
+                let num: Int = data[start..end].iter().sum();
+                if num != 0 && !is_pair_sum(window, num) {
+                    invalid_at = Some(data.len());
+                    data.push(num);
+                }
+                continue;
+            }
+
+            let a = rng.index(PREAMBLE_LEN);
+            let b = rng.index(PREAMBLE_LEN);
+            if a != b {
+                let sum = window[a] + window[b];
+                data.push(if sum <= MAX { sum } else { window[a] });
+            }
+        }
+        data.iter().join("\n")
+    }
 }
 
 /// eXchange-Masking Addition System
-struct XmasData {
+pub struct XmasData {
     data: Array<Int>,
     /// The number of previous sums to consider; also the length of the preamble
     sum_len: usize,
@@ -39,14 +90,17 @@ struct XmasData {
 
 impl XmasData {
     /// Try to create a new instance by parsing `s`
-    fn new_parse(s: &str, sum_len: usize) -> Result<Self> {
+    pub fn new_parse(s: &str, sum_len: usize) -> Result<Self, ParseError> {
         s.parse_lines()
             .map(|data| Self { data, sum_len })
-            .context("failed parsing number in XMAS encoding")
+    }
+
+    pub fn data(&self) -> &[Int] {
+        &self.data
     }
 
     /// Finds and returns the first invalid number
-    fn find_invalid_num(&self) -> Option<Int> {
+    pub fn find_invalid_num(&self) -> Option<Int> {
         // We consider the last self.sum_len numbers (starting with the preamble)
         self.data.windows(self.sum_len)
             // We then operate on every number after the preamble
@@ -57,7 +111,7 @@ impl XmasData {
     }
 
     /// Finds a contiguous set of at least 2 numbers that sum to `sum`
-    fn find_nums_that_sum_to(&self, sum: Int) -> Option<&[Int]> {
+    pub fn find_nums_that_sum_to(&self, sum: Int) -> Option<&[Int]> {
         for i in 0..self.data.len() {
             let mut set_sum = self.data[i];
             for j in i+1..self.data.len() {
@@ -70,7 +124,7 @@ impl XmasData {
     }
 
     /// Calculates the encryption weakness for this XMAS-encoded data
-    fn find_encryption_weakness(&self) -> Option<Int> {
+    pub fn find_encryption_weakness(&self) -> Option<Int> {
         let invalid_num = self.find_invalid_num()?;
         let set = self.find_nums_that_sum_to(invalid_num)?;
         let min = set.iter().min()?;
@@ -158,4 +212,23 @@ mod tests {
         let xmas_data = XmasData::new_parse(INPUT, 5).unwrap();
         assert_eq!(xmas_data.find_encryption_weakness(), Some(62));
     }
+
+    #[test]
+    fn test_generate() {
+        for (seed, &size) in (0..5).zip(&[100, 100, 1000, 5000, 20000]) {
+            let input = Day9::generate(&mut Rng::new(seed), size);
+            let nums: Vec<Int> = input.lines().map(|line| line.parse().unwrap()).collect();
+            assert!(nums.len() >= size);
+            let invalid = nums
+                .windows(PREAMBLE_LEN + 1)
+                .filter(|w| {
+                    let (window, num) = w.split_at(PREAMBLE_LEN);
+                    !window.iter().tuple_combinations().any(|(a, b)| a + b == num[0])
+                })
+                .count();
+            assert_eq!(invalid, 1, "seed {}", seed);
+            let parsed = Day9::parse(&input).unwrap();
+            Day9::part2(&parsed).unwrap();
+        }
+    }
 }
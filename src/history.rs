@@ -0,0 +1,283 @@
+//! A local log of every run's answers and timings, to spot regressions
+
+use std::{
+    collections::HashMap,
+    env,
+    fs::{self, OpenOptions},
+    io::Write,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+use anyhow::{Context, Result};
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+use crate::{answers::Status, runner::PartResult, util::*};
+
+/// The environment variable naming the history file, instead of the default
+pub static HISTORY_VAR: &str = "AOC2020_HISTORY";
+
+/// Where the history is kept: the file in [`HISTORY_VAR`] if it's set, or
+/// `history.jsonl` in this crate's directory
+pub fn history_path() -> PathBuf {
+    env::var_os(HISTORY_VAR).map_or_else(
+        || Path::new(env!("CARGO_MANIFEST_DIR")).join("history.jsonl"),
+        PathBuf::from,
+    )
+}
+
+/// Hashes an input so runs on the same input can be found (64-bit FNV-1a)
+///
+/// This is hand-rolled so the hashes in the history file stay the same
+/// across Rust versions.
+pub fn hash_input(input: &str) -> u64 {
+    input.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+/// One part of one run, as it's kept in the history file
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Entry {
+    /// When it ran, in seconds since the Unix epoch
+    pub timestamp: u64,
+    pub day: u32,
+    pub part: u8,
+    pub input: String,
+    /// The [`hash_input`] of the input, in hex, if it could be read
+    pub input_hash: Option<String>,
+    pub answer: Option<String>,
+    pub parse_ns: u64,
+    pub elapsed_ns: u64,
+    pub status: Status,
+}
+
+impl Entry {
+    pub fn new(result: &PartResult, timestamp: u64) -> Self {
+        let record = result.record();
+        Self {
+            timestamp,
+            day: record.day,
+            part: record.part,
+            input: record.input,
+            input_hash: result.input_hash.map(|hash| format!("{:016x}", hash)),
+            answer: record.answer,
+            parse_ns: record.parse_ns,
+            elapsed_ns: record.elapsed_ns,
+            status: record.status,
+        }
+    }
+}
+
+/// The current time, in seconds since the Unix epoch
+pub fn now() -> u64 {
+    SystemTime::now().duration_since(SystemTime::UNIX_EPOCH)
+        .map_or(0, |since| since.as_secs())
+}
+
+/// Adds `results` to the end of the history file at `path`
+pub fn append(path: &Path, results: &[PartResult], timestamp: u64) -> Result<()> {
+    let mut lines = Vec::new();
+    for result in results {
+        serde_json::to_writer(&mut lines, &Entry::new(result, timestamp))?;
+        lines.push(b'\n');
+    }
+    OpenOptions::new().create(true).append(true).open(path)
+        .and_then(|mut file| file.write_all(&lines))
+        .with_context(|| format!("failed to add to history file {}", path.display()))
+}
+
+/// Reads every entry in the history file at `path`, oldest first
+///
+/// There's no history if the file doesn't exist yet.
+pub fn load(path: &Path) -> Result<Vec<Entry>> {
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let history = fs::read_to_string(path)
+        .with_context(|| format!("failed to read history file {}", path.display()))?;
+    history.lines().enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| serde_json::from_str(line)
+            .with_context(|| format!("invalid entry on line {} of {}", i + 1, path.display())))
+        .try_collect()
+}
+
+/// An answer that's different from the one before it for the same input,
+/// which means something broke
+#[derive(Clone, Debug, PartialEq)]
+pub struct AnswerChange {
+    pub day: u32,
+    pub part: u8,
+    pub input: String,
+    pub input_hash: String,
+    pub before: String,
+    pub after: String,
+    pub timestamp: u64,
+}
+
+/// Finds every time an answer changed for the same day, part and input hash
+///
+/// Runs that failed, or couldn't read their input, are skipped.
+pub fn find_answer_changes(entries: &[Entry]) -> Vec<AnswerChange> {
+    let mut last_answers: HashMap<(u32, u8, &str), &str> = HashMap::new();
+    let mut changes = Vec::new();
+    for entry in entries {
+        let (hash, answer) = match (&entry.input_hash, &entry.answer) {
+            (Some(hash), Some(answer)) => (hash, answer),
+            _ => continue,
+        };
+        let before = last_answers.insert((entry.day, entry.part, hash), answer);
+        if let Some(before) = before.filter(|&before| before != answer) {
+            changes.push(AnswerChange {
+                day: entry.day,
+                part: entry.part,
+                input: entry.input.clone(),
+                input_hash: hash.clone(),
+                before: before.into(),
+                after: answer.clone(),
+                timestamp: entry.timestamp,
+            });
+        }
+    }
+    changes
+}
+
+/// How long ago `timestamp` was, roughly, like "3h ago"
+fn format_age(timestamp: u64, now: u64) -> String {
+    let secs = now.saturating_sub(timestamp);
+    match secs {
+        0..=59 => format!("{}s ago", secs),
+        60..=3599 => format!("{}m ago", secs / 60),
+        3600..=86399 => format!("{}h ago", secs / 3600),
+        _ => format!("{}d ago", secs / 86400),
+    }
+}
+
+/// Prints a table of each day, part and input's runs in `entries`, then
+/// every answer that changed for the same input
+///
+/// The trend compares the latest time to the median of the runs before it.
+/// Returns whether no answers changed.
+pub fn report(entries: &[Entry], now: u64) -> bool {
+    fn format_ns(ns: u64) -> String {
+        format!("{:.2?}", Duration::from_nanos(ns))
+    }
+    fn median(times: &[u64]) -> u64 {
+        times.iter().copied().sorted().nth(times.len() / 2).unwrap_or(0)
+    }
+
+    let changes = find_answer_changes(entries);
+    let groups = entries.iter()
+        .map(|entry| ((entry.day, entry.part, entry.input.as_str()), entry))
+        .into_group_map();
+
+    let rows = groups.into_iter().sorted_by_key(|(key, _)| *key).map(|((day, part, input), runs)| {
+        let latest = runs.last().expect("groups aren't empty");
+        let times = runs.iter().map(|entry| entry.elapsed_ns).collect_vec();
+        let previous = &times[..times.len() - 1];
+        let trend = if previous.is_empty() {
+            "-".to_owned()
+        } else {
+            let base = median(previous);
+            format!("{:+.1}%", (latest.elapsed_ns as f64 - base as f64) / base.max(1) as f64 * 100.0)
+        };
+        let changed = changes.iter().any(|change|
+            change.day == day && change.part == part && change.input == input);
+        vec![
+            day.to_string(),
+            part.to_string(),
+            input.to_owned(),
+            runs.len().to_string(),
+            latest.answer.clone().unwrap_or_else(|| "error".into()),
+            latest.status.to_string(),
+            if changed { "CHANGED".into() } else { "same".into() },
+            format_ns(latest.elapsed_ns),
+            format_ns(median(&times)),
+            trend,
+            format_age(latest.timestamp, now),
+        ]
+    }).collect_vec();
+    print_table(
+        &["Day", "Part", "Input", "Runs", "Answer", "Status", "Answers", "Latest", "Median", "Trend", "Last run"],
+        &rows,
+    );
+
+    if !changes.is_empty() {
+        println!();
+    }
+    for change in &changes {
+        println!(
+            "Day {} part {} on {} (input {}) changed from {} to {}, {}",
+            change.day, change.part, change.input, change.input_hash,
+            change.before, change.after, format_age(change.timestamp, now),
+        );
+    }
+    changes.is_empty()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(timestamp: u64, input_hash: &str, answer: Option<&str>) -> Entry {
+        Entry {
+            timestamp,
+            day: 7,
+            part: 1,
+            input: "day7".into(),
+            input_hash: Some(input_hash.into()),
+            answer: answer.map(Into::into),
+            parse_ns: 10,
+            elapsed_ns: 100,
+            status: Status::Unknown,
+        }
+    }
+
+    #[test]
+    fn test_hash_input() {
+        assert_eq!(hash_input(""), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(hash_input("a"), 0xaf63_dc4c_8601_ec8c);
+        assert_ne!(hash_input("1\n2\n"), hash_input("2\n1\n"));
+    }
+
+    #[test]
+    fn test_find_answer_changes() {
+        let entries = [
+            entry(1, "aaaa", Some("278")),
+            entry(2, "bbbb", Some("300")),
+            entry(3, "aaaa", None),
+            entry(4, "aaaa", Some("278")),
+            entry(5, "aaaa", Some("279")),
+        ];
+        let changes = find_answer_changes(&entries);
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].input_hash, "aaaa");
+        assert_eq!((changes[0].before.as_str(), changes[0].after.as_str()), ("278", "279"));
+        assert_eq!(changes[0].timestamp, 5);
+    }
+
+    #[test]
+    fn test_append_and_load() {
+        let path = env::temp_dir().join(format!("aoc2020-history-test-{}.jsonl", std::process::id()));
+        let _ = fs::remove_file(&path);
+        assert!(load(&path).unwrap().is_empty());
+
+        let day = &crate::DAYS[0];
+        let results = crate::runner::solve(
+            day, "example", "1721\n979\n366\n299\n675\n1456", Parts::Both, &Default::default()
+        );
+        append(&path, &results, 100).unwrap();
+        append(&path, &results[..1], 200).unwrap();
+        let entries = load(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries[0].answer.as_deref(), Some("514579"));
+        assert_eq!(entries[2].timestamp, 200);
+        assert_eq!(
+            entries[0].input_hash,
+            Some(format!("{:016x}", hash_input("1721\n979\n366\n299\n675\n1456")))
+        );
+        assert!(find_answer_changes(&entries).is_empty());
+    }
+}
@@ -0,0 +1,77 @@
+//! Solutions to [Advent of Code 2020](https://adventofcode.com/2020)
+//!
+//! Each day has its own module, with a type implementing [`Solution`] and the
+//! domain types its puzzle needed. [`DAYS`] lists every solved day.
+
+#[macro_use]
+pub mod util;
+pub mod allocs;
+pub mod answers;
+pub mod bench;
+pub mod cli;
+pub mod history;
+pub mod runner;
+pub mod scaffold;
+pub mod serve;
+pub mod day1;
+pub mod day2;
+pub mod day3;
+pub mod day4;
+pub mod day5;
+pub mod day6;
+pub mod day7;
+pub mod day8;
+pub mod day9;
+
+use crate::util::{ParseError, Parsed, Rng, Solution, input_variants, parse_erased};
+
+/// A day's puzzle, and its solution with the types erased
+pub struct Day {
+    pub number: u32,
+    parse: for<'a> fn(&'a str) -> Result<Box<dyn Parsed + 'a>, ParseError>,
+    generate: fn(&mut Rng, usize) -> String,
+}
+
+impl Day {
+    pub const fn new<S: Solution + 'static>(number: u32) -> Self {
+        Self { number, parse: parse_erased::<S>, generate: S::generate }
+    }
+
+    /// The name of this day's default input file
+    pub fn input_name(&self) -> String {
+        format!("day{}", self.number)
+    }
+
+    /// The names of every input for this day: the default one and any named
+    /// variants, like `day7/alice`
+    ///
+    /// If there aren't any, it's just the default one so reading it gives an
+    /// error saying where it looked.
+    pub fn input_names(&self) -> Vec<String> {
+        let names = input_variants(&self.input_name());
+        if names.is_empty() { vec![self.input_name()] } else { names }
+    }
+
+    /// Parses `input` for this day's solution
+    pub fn parse<'a>(&self, input: &'a str) -> Result<Box<dyn Parsed + 'a>, ParseError> {
+        (self.parse)(input)
+    }
+
+    /// Generates a random valid input for this day with roughly `size` records
+    pub fn generate(&self, rng: &mut Rng, size: usize) -> String {
+        (self.generate)(rng, size)
+    }
+}
+
+/// Every solved day, in order
+pub static DAYS: &[Day] = &[
+    Day::new::<day1::Day1>(1),
+    Day::new::<day2::Day2>(2),
+    Day::new::<day3::Day3>(3),
+    Day::new::<day4::Day4>(4),
+    Day::new::<day5::Day5>(5),
+    Day::new::<day6::Day6>(6),
+    Day::new::<day7::Day7>(7),
+    Day::new::<day8::Day8>(8),
+    Day::new::<day9::Day9>(9),
+];
@@ -1,24 +1,3 @@
-#[macro_use]
-#[allow(dead_code, unused_macros)]
-mod util;
-// mod day1;
-// mod day2;
-// mod day3;
-// mod day4;
-// mod day5;
-// mod day6;
-// mod day7;
-// mod day8;
-mod day9;
-
-fn main() {
-    // day1::day1();
-    // day2::day2();
-    // day3::day3();
-    // day4::day4();
-    // day5::day5();
-    // day6::day6();
-    // day7::day7();
-    // day8::day8();
-    day9::day9();
+fn main() -> anyhow::Result<()> {
+    aoc2020::cli::main()
 }
@@ -0,0 +1,481 @@
+//! Runs days' solutions, timing them and checking their answers
+
+use std::{
+    fmt::Display,
+    io::{self, Write},
+    panic::{self, AssertUnwindSafe},
+    str::FromStr,
+    sync::{Mutex, atomic::{AtomicUsize, Ordering}},
+    thread,
+    time::{Duration, Instant},
+};
+use anyhow::{Context, Error, Result, bail};
+use serde::Serialize;
+use crate::{
+    Day,
+    allocs::{self, AllocStats},
+    answers::{Answers, Status},
+    history::hash_input,
+    util::*,
+};
+
+/// The outcome of solving one part of one day's puzzle
+#[derive(Debug)]
+pub struct PartResult {
+    pub day: u32,
+    pub part: Part,
+    /// The name of the input it was solved for
+    pub input: String,
+    /// The [`hash_input`] of the input, unless it couldn't be read
+    pub input_hash: Option<u64>,
+    pub answer: Result<String>,
+    /// How long parsing the input took; it's shared by both parts
+    pub parse_time: Duration,
+    /// How long solving this part took, not counting parsing
+    pub time: Duration,
+    /// The allocations parsing made, if they're being counted; they're shared
+    /// by both parts
+    pub parse_allocs: Option<AllocStats>,
+    /// The allocations solving this part made, if they're being counted
+    pub allocs: Option<AllocStats>,
+    /// What had to be normalized in the input when it was read; it's shared
+    /// by both parts
+    pub normalization: Normalization,
+    pub status: Status,
+}
+
+impl PartResult {
+    /// The answer, or the first line of the error (parse errors show the
+    /// offending line of input after that)
+    pub fn answer_summary(&self) -> String {
+        match &self.answer {
+            Ok(answer) => answer.clone(),
+            Err(e) => format!("error: {:#}", e).lines().next().unwrap_or_default().to_owned(),
+        }
+    }
+
+    /// The allocations parsing and solving made, if they're being counted
+    pub fn alloc_summary(&self) -> Option<String> {
+        let (parse, solve) = (self.parse_allocs?, self.allocs?);
+        Some(format!(
+            "parsing made {} allocations with {} peak, solving {} with {} peak",
+            parse.allocations, format_bytes(parse.peak_bytes),
+            solve.allocations, format_bytes(solve.peak_bytes),
+        ))
+    }
+
+    /// Flattens this into a record for machine-readable output
+    pub fn record(&self) -> Record {
+        let (answer, error) = match &self.answer {
+            Ok(answer) => (Some(answer.clone()), None),
+            Err(e) => (None, Some(format!("{:#}", e))),
+        };
+        Record {
+            day: self.day,
+            part: self.part.number(),
+            input: self.input.clone(),
+            answer,
+            error,
+            parse_ns: self.parse_time.as_nanos() as u64,
+            elapsed_ns: self.time.as_nanos() as u64,
+            parse_allocations: self.parse_allocs.map(|stats| stats.allocations),
+            parse_peak_bytes: self.parse_allocs.map(|stats| stats.peak_bytes),
+            allocations: self.allocs.map(|stats| stats.allocations),
+            peak_bytes: self.allocs.map(|stats| stats.peak_bytes),
+            status: self.status,
+        }
+    }
+}
+
+/// A `PartResult` as it's written out in JSON and CSV
+#[derive(Clone, Debug, Serialize)]
+pub struct Record {
+    pub day: u32,
+    pub part: u8,
+    pub input: String,
+    pub answer: Option<String>,
+    /// The full error, including the offending line for parse errors
+    pub error: Option<String>,
+    pub parse_ns: u64,
+    pub elapsed_ns: u64,
+    /// The allocation stats are only there with the `count-allocs` feature
+    pub parse_allocations: Option<u64>,
+    pub parse_peak_bytes: Option<u64>,
+    pub allocations: Option<u64>,
+    pub peak_bytes: Option<u64>,
+    pub status: Status,
+}
+
+/// How results are printed
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Format {
+    /// For people to read
+    Text,
+    /// One JSON object per line
+    Json,
+    /// A header row, then one row per result
+    Csv,
+}
+
+impl FromStr for Format {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "text" => Format::Text,
+            "json" => Format::Json,
+            "csv" => Format::Csv,
+            _ => bail!("expected format text, json or csv but found '{}'", s),
+        })
+    }
+}
+
+/// Parses `input`, named `input_name`, and solves `parts` of `day`, timing
+/// each stage and checking the answers against `answers`
+///
+/// If parsing fails, every part gets the parse error.
+pub fn solve(
+    day: &Day, input_name: &str, input: &str, parts: Parts, answers: &Answers
+) -> Vec<PartResult> {
+    let input_hash = hash_input(input);
+    let ((parsed, parse_time), parse_allocs) = allocs::measure(|| {
+        let start = Instant::now();
+        (day.parse(input), start.elapsed())
+    });
+
+    parts.iter().map(|part| {
+        let ((answer, time), allocs) = allocs::measure(|| {
+            let start = Instant::now();
+            let answer = match &parsed {
+                Ok(parsed) => parsed.solve(part)
+                    .with_context(|| format!("failed to solve day {} part {}", day.number, part)),
+                Err(e) => Err(Error::new(e.clone().with_input(input_name))
+                    .context(format!("failed to parse input for day {}", day.number))),
+            };
+            (answer, start.elapsed())
+        });
+
+        PartResult {
+            day: day.number,
+            part,
+            input: input_name.into(),
+            input_hash: Some(input_hash),
+            status: answers.check(day.number, part, input_name, &answer),
+            answer,
+            parse_time,
+            time,
+            parse_allocs,
+            allocs,
+            normalization: Normalization::default(),
+        }
+    }).collect()
+}
+
+/// Solves `parts` of `day` for each of its inputs, read in `mode`
+///
+/// If an input can't be read, or the solution panics on it, every part gets
+/// that error.
+pub fn run_day(day: &Day, parts: Parts, answers: &Answers, mode: InputMode) -> Vec<PartResult> {
+    day.input_names().into_iter()
+        .flat_map(|input_name| {
+            let run = || run_input(day, &input_name, parts, answers, mode);
+            unwrap_or!(panic::catch_unwind(AssertUnwindSafe(run)), e => failed(
+                day, &input_name, parts, &format_args!("day {} panicked: {}", day.number, panic_message(&*e))
+            ))
+        })
+        .collect()
+}
+
+fn run_input(
+    day: &Day, input_name: &str, parts: Parts, answers: &Answers, mode: InputMode
+) -> Vec<PartResult> {
+    match get_input_with(input_name, mode) {
+        Ok((input, normalization)) => {
+            let mut results = solve(day, input_name, &input, parts, answers);
+            for result in &mut results {
+                result.normalization = normalization.clone();
+            }
+            results
+        }
+        Err(e) => failed(day, input_name, parts, &format_args!("{:#}", e)),
+    }
+}
+
+/// Results for `parts` of `day` that all failed before they could be solved
+fn failed(day: &Day, input_name: &str, parts: Parts, error: &dyn Display) -> Vec<PartResult> {
+    parts.iter().map(|part| PartResult {
+        day: day.number,
+        part,
+        input: input_name.into(),
+        input_hash: None,
+        answer: Err(Error::msg(error.to_string())),
+        parse_time: Duration::default(),
+        time: Duration::default(),
+        parse_allocs: None,
+        allocs: None,
+        normalization: Normalization::default(),
+        status: Status::Fail,
+    }).collect()
+}
+
+/// Runs every one of `days` on `jobs` worker threads
+///
+/// The results come back in the same order as `days`.
+pub fn run_all(
+    days: &[&Day], parts: Parts, answers: &Answers, mode: InputMode, jobs: usize
+) -> Vec<PartResult> {
+    let next_day = AtomicUsize::new(0);
+    let results = Mutex::new(Vec::new());
+
+    thread::scope(|scope| {
+        for _ in 0..jobs.clamp(1, days.len().max(1)) {
+            scope.spawn(|| {
+                while let Some(day) = days.get(next_day.fetch_add(1, Ordering::Relaxed)) {
+                    let day_results = run_day(day, parts, answers, mode);
+                    results.lock().unwrap().extend(day_results);
+                }
+            });
+        }
+    });
+
+    let mut results = results.into_inner().unwrap();
+    results.sort_by_cached_key(|result| (
+        days.iter().position(|day| day.number == result.day),
+        result.input.clone(),
+        result.part,
+    ));
+    results
+}
+
+/// The default number of worker threads
+pub fn default_jobs() -> usize {
+    thread::available_parallelism().map_or(1, |n| n.get())
+}
+
+/// Formats a number of bytes with a binary unit, like "1.5 KiB"
+fn format_bytes(bytes: u64) -> String {
+    let mut size = bytes as f64;
+    for unit in &["B", "KiB", "MiB"] {
+        if size < 1024.0 {
+            return if *unit == "B" { format!("{} B", bytes) } else { format!("{:.1} {}", size, unit) };
+        }
+        size /= 1024.0;
+    }
+    format!("{:.1} GiB", size)
+}
+
+/// Prints `results` as a table, with a total at the bottom
+///
+/// With the `count-allocs` feature, it includes each stage's allocations.
+/// Returns whether none of them failed.
+pub fn print_summary(results: &[PartResult]) -> bool {
+    let rows: Vec<_> = results.iter().map(|result| {
+        let mut row = vec![
+            result.day.to_string(),
+            result.part.to_string(),
+            result.input.clone(),
+            result.answer_summary(),
+            result.status.to_string(),
+            format!("{:.2?}", result.parse_time),
+            format!("{:.2?}", result.time),
+        ];
+        for stats in [result.parse_allocs, result.allocs].iter().flatten() {
+            row.push(stats.allocations.to_string());
+            row.push(format_bytes(stats.peak_bytes));
+        }
+        row
+    }).collect();
+    let mut headers = vec!["Day", "Part", "Input", "Answer", "Status", "Parse", "Time"];
+    if allocs::enabled() {
+        headers.extend(&["Parse allocs", "Parse peak", "Allocs", "Peak"]);
+    }
+    print_table(&headers, &rows);
+
+    let count = |status| results.iter().filter(|result| result.status == status).count();
+    let failed = count(Status::Fail);
+    println!(
+        "\n{} passed, {} failed, {} unknown",
+        count(Status::Pass), failed, count(Status::Unknown)
+    );
+    failed == 0
+}
+
+/// Prints `results` in `format`; text gets the summary table
+///
+/// Returns whether none of them failed.
+pub fn print_results(results: &[PartResult], format: Format) -> Result<bool> {
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+    match format {
+        Format::Text => return Ok(print_summary(results)),
+        Format::Json => write_json_lines(&mut out, results)?,
+        Format::Csv => write_csv(&mut out, results)?,
+    }
+    Ok(results.iter().all(|result| result.status != Status::Fail))
+}
+
+/// Writes each of `results` as a JSON object on its own line
+pub fn write_json_lines(mut out: impl Write, results: &[PartResult]) -> Result<()> {
+    for result in results {
+        serde_json::to_writer(&mut out, &result.record())?;
+        writeln!(out)?;
+    }
+    Ok(())
+}
+
+/// Writes `results` as CSV with a header row
+pub fn write_csv(mut out: impl Write, results: &[PartResult]) -> Result<()> {
+    /// Quotes `field` if it has anything CSV treats specially
+    fn escape(field: &str) -> String {
+        if field.contains(&[',', '"', '\n', '\r'][..]) {
+            format!("\"{}\"", field.replace('"', "\"\""))
+        } else {
+            field.to_owned()
+        }
+    }
+
+    fn optional(n: Option<u64>) -> String {
+        n.map_or_else(String::new, |n| n.to_string())
+    }
+
+    writeln!(
+        out,
+        "day,part,input,answer,error,parse_ns,elapsed_ns,\
+         parse_allocations,parse_peak_bytes,allocations,peak_bytes,status"
+    )?;
+    for result in results {
+        let record = result.record();
+        writeln!(
+            out,
+            "{},{},{},{},{},{},{},{},{},{},{},{}",
+            record.day,
+            record.part,
+            escape(&record.input),
+            escape(record.answer.as_deref().unwrap_or_default()),
+            escape(record.error.as_deref().unwrap_or_default()),
+            record.parse_ns,
+            record.elapsed_ns,
+            optional(record.parse_allocations),
+            optional(record.parse_peak_bytes),
+            optional(record.allocations),
+            optional(record.peak_bytes),
+            record.status.name(),
+        )?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DAYS;
+
+    #[test]
+    fn test_solve_checks_answers() {
+        let answers: Answers = "1 1 example 514579\n1 2 example 42".parse().unwrap();
+        let input = "1721\n979\n366\n299\n675\n1456";
+        let results = solve(&DAYS[0], "example", input, Parts::Both, &answers);
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].status, Status::Pass);
+        assert_eq!(results[1].status, Status::Fail);
+        assert_eq!(results[1].answer.as_deref().unwrap(), "241861950");
+    }
+
+    #[test]
+    fn test_solve_parse_error() {
+        let results = solve(&DAYS[0], "bad", "1721\nxyz", Parts::One, &Answers::default());
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].status, Status::Fail);
+        assert!(results[0].answer_summary().contains("failed to parse input for day 1"));
+    }
+
+    #[test]
+    fn test_run_all_survives_panics() {
+        struct Panics;
+        impl Solution for Panics {
+            type Input<'a> = ();
+            type Answer = i32;
+            fn parse(_: &str) -> Result<(), ParseError> { Ok(()) }
+            fn part1(_: &()) -> Result<i32> { panic!("oh no") }
+            fn part2(_: &()) -> Result<i32> { Ok(2) }
+            fn generate(_: &mut Rng, _: usize) -> String { String::new() }
+        }
+
+        // It reads day 1's input, and panics on it
+        let panics = Day::new::<Panics>(1);
+        let results = run_all(&[&panics, &DAYS[1]], Parts::Both, &Answers::default(), InputMode::Normalize, 2);
+        assert_eq!(results.len(), 4);
+        assert!(results[..2].iter().all(|result| result.status == Status::Fail));
+        assert_eq!(results[0].answer_summary(), "error: day 1 panicked: oh no");
+        assert!(results[2..].iter().all(|result| result.day == 2 && result.answer.is_ok()));
+    }
+
+    #[test]
+    fn test_solve_generated_inputs() {
+        // Even the smallest inputs each day generates have to be solvable, as
+        // `gen` writes them out
+        for day in DAYS {
+            for &size in &[0, 1, 10] {
+                let input = day.generate(&mut Rng::new(size as u64), size) + "\n";
+                let (input, _) = InputMode::Strict.apply("generated", input).unwrap();
+                for result in solve(day, "generated", &input, Parts::Both, &Answers::default()) {
+                    assert!(result.answer.is_ok(), "day {} size {}: {:#}", day.number, size, result.answer.unwrap_err());
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_format_bytes() {
+        assert_eq!(format_bytes(10), "10 B");
+        assert_eq!(format_bytes(1536), "1.5 KiB");
+        assert_eq!(format_bytes(3 << 20), "3.0 MiB");
+        assert_eq!(format_bytes(5 << 30), "5.0 GiB");
+    }
+
+    #[test]
+    fn test_solve_counts_allocations() {
+        let results = solve(&DAYS[0], "example", "1721\n979\n366", Parts::Both, &Answers::default());
+        assert_eq!(results[0].allocs.is_some(), allocs::enabled());
+        if let Some(stats) = results[0].parse_allocs {
+            assert!(stats.allocations > 0);
+        }
+    }
+
+    #[test]
+    fn test_json_lines() {
+        let answers: Answers = "1 1 example 514579".parse().unwrap();
+        let input = "1721\n979\n366\n299\n675\n1456";
+        let results = solve(&DAYS[0], "example", input, Parts::Both, &answers);
+        let mut out = Vec::new();
+        write_json_lines(&mut out, &results).unwrap();
+
+        let lines: Vec<serde_json::Value> = String::from_utf8(out).unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["day"], 1);
+        assert_eq!(lines[0]["part"], 1);
+        assert_eq!(lines[0]["answer"], "514579");
+        assert_eq!(lines[0]["error"], serde_json::Value::Null);
+        assert_eq!(lines[0]["status"], "pass");
+        assert_eq!(lines[1]["status"], "unknown");
+    }
+
+    #[test]
+    fn test_csv_quotes_errors() {
+        let results = solve(&DAYS[0], "bad, input", "1721\nxyz", Parts::One, &Answers::default());
+        let mut out = Vec::new();
+        write_csv(&mut out, &results).unwrap();
+        let out = String::from_utf8(out).unwrap();
+
+        let mut lines = out.lines();
+        assert_eq!(lines.next(), Some(
+            "day,part,input,answer,error,parse_ns,elapsed_ns,\
+             parse_allocations,parse_peak_bytes,allocations,peak_bytes,status"
+        ));
+        assert!(lines.next().unwrap().starts_with("1,1,\"bad, input\",,\"failed to parse"));
+        assert!(out.trim_end().ends_with(",fail"));
+    }
+}
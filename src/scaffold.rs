@@ -0,0 +1,190 @@
+//! Generates the boilerplate for a new day
+
+use std::{fs, path::{Path, PathBuf}};
+use anyhow::{Context, Result, bail};
+
+/// The template for a new day's module; `DAY_NUMBER` is replaced with the day
+static TEMPLATE: &str = r#"use anyhow::{Result, bail};
+use itertools::Itertools;
+use crate::util::*;
+
+pub struct DayDAY_NUMBER;
+
+impl Solution for DayDAY_NUMBER {
+    type Input<'a> = Vec<i64>;
+    type Answer = i64;
+
+    fn parse(input: &str) -> Result<Vec<i64>, ParseError> {
+        input.parse_lines()
+    }
+
+    fn part1(_input: &Vec<i64>) -> Result<i64> {
+        bail!("not solved yet")
+    }
+
+    fn part2(_input: &Vec<i64>) -> Result<i64> {
+        bail!("not solved yet")
+    }
+
+    fn generate(rng: &mut Rng, size: usize) -> String {
+        (0..size).map(|_| rng.range(0..1000)).join("\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    static INPUT: &str = "\
+";
+
+    #[test]
+    #[ignore = "needs the example from the puzzle"]
+    fn test_part1() {
+        let input = DayDAY_NUMBER::parse(INPUT).unwrap();
+        assert_eq!(DayDAY_NUMBER::part1(&input).unwrap(), 0);
+    }
+
+    #[test]
+    #[ignore = "needs the example from the puzzle"]
+    fn test_part2() {
+        let input = DayDAY_NUMBER::parse(INPUT).unwrap();
+        assert_eq!(DayDAY_NUMBER::part2(&input).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_generate() {
+        for seed in 0..5 {
+            let input = DayDAY_NUMBER::generate(&mut Rng::new(seed), 100);
+            DayDAY_NUMBER::parse(&input).unwrap();
+        }
+    }
+}
+"#;
+
+/// The root of this crate's source, where new days are added
+pub fn crate_root() -> &'static Path {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+}
+
+/// Renders the module for `day` from the template
+pub fn render_module(day: u32) -> String {
+    TEMPLATE.replace("DAY_NUMBER", &day.to_string())
+}
+
+/// Adds `day`'s module declaration and registry entry to the source of `lib.rs`,
+/// keeping both in order
+pub fn register_day(lib_src: &str, day: u32) -> Result<String> {
+    let mut lines: Vec<String> = lib_src.lines().map(String::from).collect();
+
+    fn insert_sorted(
+        lines: &mut Vec<String>, day: u32, line: String,
+        number_of: impl Fn(&str) -> Option<u32>,
+    ) -> Result<()> {
+        let existing = lines.iter().enumerate()
+            .filter_map(|(i, line)| number_of(line).map(|n| (i, n)))
+            .collect::<Vec<_>>();
+        if existing.iter().any(|&(_, n)| n == day) {
+            bail!("day {} is already registered", day);
+        }
+        let &(last_idx, _) = existing.last()
+            .context("couldn't find where to register the day")?;
+        let idx = existing.iter()
+            .find(|&&(_, n)| n > day)
+            .map_or(last_idx + 1, |&(i, _)| i);
+        lines.insert(idx, line);
+        Ok(())
+    }
+
+    insert_sorted(&mut lines, day, format!("pub mod day{};", day), |line| line
+        .strip_prefix("pub mod day")?
+        .strip_suffix(';')?
+        .parse().ok())?;
+    insert_sorted(&mut lines, day, format!("    Day::new::<day{0}::Day{0}>({0}),", day), |line| line
+        .trim_start()
+        .strip_prefix("Day::new::<day")?
+        .split("::")
+        .next()?
+        .parse().ok())?;
+
+    let mut src = lines.join("\n");
+    src.push('\n');
+    Ok(src)
+}
+
+/// Creates `day`'s module and an empty input file under `root`, and registers it
+///
+/// Returns the paths of the files it created or changed.
+pub fn new_day(root: &Path, day: u32) -> Result<Vec<PathBuf>> {
+    let module_path = root.join("src").join(format!("day{}.rs", day));
+    let input_path = root.join("inputs").join(format!("day{}.txt", day));
+    let lib_path = root.join("src").join("lib.rs");
+
+    if module_path.exists() {
+        bail!("{} already exists", module_path.display());
+    }
+    let lib_src = fs::read_to_string(&lib_path)
+        .with_context(|| format!("failed to read {}", lib_path.display()))?;
+    let lib_src = register_day(&lib_src, day)?;
+
+    fs::write(&module_path, render_module(day))
+        .with_context(|| format!("failed to write {}", module_path.display()))?;
+    if !input_path.exists() {
+        fs::write(&input_path, "")
+            .with_context(|| format!("failed to write {}", input_path.display()))?;
+    }
+    fs::write(&lib_path, lib_src)
+        .with_context(|| format!("failed to write {}", lib_path.display()))?;
+
+    Ok(vec![module_path, input_path, lib_path])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    static LIB: &str = "\
+pub mod day1;
+pub mod day2;
+pub mod day4;
+
+pub static DAYS: &[Day] = &[
+    Day::new::<day1::Day1>(1),
+    Day::new::<day2::Day2>(2),
+    Day::new::<day4::Day4>(4),
+];
+";
+
+    #[test]
+    fn test_register_day() {
+        assert_eq!(register_day(LIB, 3).unwrap(), "\
+pub mod day1;
+pub mod day2;
+pub mod day3;
+pub mod day4;
+
+pub static DAYS: &[Day] = &[
+    Day::new::<day1::Day1>(1),
+    Day::new::<day2::Day2>(2),
+    Day::new::<day3::Day3>(3),
+    Day::new::<day4::Day4>(4),
+];
+");
+        let src = register_day(LIB, 10).unwrap();
+        assert!(src.contains("pub mod day4;\npub mod day10;\n"));
+        assert!(src.contains("(4),\n    Day::new::<day10::Day10>(10),\n];"));
+    }
+
+    #[test]
+    fn test_register_existing_day() {
+        assert!(register_day(LIB, 2).is_err());
+    }
+
+    #[test]
+    fn test_render_module() {
+        let src = render_module(12);
+        assert!(src.contains("pub struct Day12;"));
+        assert!(src.contains("impl Solution for Day12 {"));
+        assert!(!src.contains("DAY_NUMBER"));
+    }
+}
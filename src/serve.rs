@@ -0,0 +1,283 @@
+//! A small local HTTP server exposing the solvers as a JSON API
+//!
+//! `GET /days` lists the solved days. `POST /solve/<day>` or
+//! `POST /solve/<day>/<part>` with the puzzle input as the body solves it
+//! and responds with each part's answer and timings, like `--format json`
+//! does, plus where the input failed to parse, if it did.
+
+use std::{
+    io::{BufRead, BufReader, Write},
+    net::{TcpListener, TcpStream},
+    panic::{self, AssertUnwindSafe},
+    thread,
+    time::Duration,
+};
+use anyhow::{Context, Result};
+use serde::Serialize;
+use serde_json::{Value, json};
+use crate::{DAYS, answers::Answers, runner, util::*};
+
+/// The biggest request body accepted, which is far bigger than any real input
+const MAX_BODY_LEN: usize = 16 << 20;
+
+/// The name given to inputs sent in requests, in results and errors
+const INPUT_NAME: &str = "request";
+
+/// Serves the API on `port` on localhost until the process is stopped
+pub fn serve(port: u16) -> Result<()> {
+    let listener = TcpListener::bind(("127.0.0.1", port))
+        .with_context(|| format!("failed to listen on port {}", port))?;
+    println!("Listening on http://{}", listener.local_addr()?);
+    serve_on(listener);
+    Ok(())
+}
+
+/// Handles every connection to `listener`, each on its own thread
+pub fn serve_on(listener: TcpListener) {
+    for stream in listener.incoming() {
+        let stream = unwrap_or!(stream, e => {
+            eprintln!("warning: failed to accept a connection: {}", e);
+            continue;
+        });
+        thread::spawn(move || {
+            if let Err(e) = handle_connection(stream) {
+                eprintln!("warning: {:#}", e);
+            }
+        });
+    }
+}
+
+/// A response, before it's written out
+#[derive(Debug, PartialEq)]
+pub struct Response {
+    pub status: u16,
+    pub body: Value,
+}
+
+impl Response {
+    fn ok(body: Value) -> Self {
+        Self { status: 200, body }
+    }
+
+    fn error(status: u16, message: impl std::fmt::Display) -> Self {
+        Self { status, body: json!({ "error": message.to_string() }) }
+    }
+
+    fn reason(&self) -> &'static str {
+        match self.status {
+            200 => "OK",
+            400 => "Bad Request",
+            404 => "Not Found",
+            405 => "Method Not Allowed",
+            413 => "Payload Too Large",
+            500 => "Internal Server Error",
+            _ => "Error",
+        }
+    }
+}
+
+fn handle_connection(stream: TcpStream) -> Result<()> {
+    stream.set_read_timeout(Some(Duration::from_secs(30)))?;
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let response = match read_request(&mut reader) {
+        Ok((method, path, body)) => handle(&method, &path, &body),
+        Err(response) => response,
+    };
+
+    let body = response.body.to_string();
+    let mut stream = stream;
+    write!(
+        stream,
+        "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        response.status, response.reason(), body.len(), body,
+    ).context("failed to write response")
+}
+
+/// Reads a request's method, path and body, or the error response to send
+fn read_request(reader: &mut impl BufRead) -> Result<(String, String, Vec<u8>), Response> {
+    fn read_line(reader: &mut impl BufRead) -> Result<String, Response> {
+        let mut line = String::new();
+        reader.read_line(&mut line)
+            .map_err(|e| Response::error(400, format_args!("failed to read request: {}", e)))?;
+        Ok(line.trim_end().to_owned())
+    }
+
+    let request_line = read_line(reader)?;
+    let mut words = request_line.split(' ');
+    let (method, path) = match (words.next(), words.next()) {
+        (Some(method), Some(path)) if !method.is_empty() => (method.to_owned(), path.to_owned()),
+        _ => return Err(Response::error(400, "invalid request line")),
+    };
+
+    let mut content_len = 0;
+    loop {
+        let header = read_line(reader)?;
+        if header.is_empty() { break; }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                content_len = value.trim().parse()
+                    .map_err(|_| Response::error(400, "invalid Content-Length"))?;
+            }
+        }
+    }
+    if content_len > MAX_BODY_LEN {
+        return Err(Response::error(413, format_args!("inputs can be at most {} bytes", MAX_BODY_LEN)));
+    }
+
+    let mut body = vec![0; content_len];
+    reader.read_exact(&mut body)
+        .map_err(|e| Response::error(400, format_args!("failed to read request body: {}", e)))?;
+    Ok((method, path, body))
+}
+
+/// Where an input failed to parse
+#[derive(Debug, Serialize)]
+struct Diagnostic {
+    line: usize,
+    column: usize,
+    len: usize,
+    line_text: String,
+    message: String,
+    /// The whole error, with the line and a caret under the problem
+    rendered: String,
+}
+
+/// Responds to a request for `path`
+pub fn handle(method: &str, path: &str, body: &[u8]) -> Response {
+    let segments: Vec<_> = path.trim_matches('/').split('/').collect();
+    match (method, &segments[..]) {
+        ("GET", ["days"]) => Response::ok(json!({
+            "days": DAYS.iter().map(|day| day.number).collect::<Vec<_>>(),
+        })),
+        ("POST", ["solve", day]) => solve(day, None, body),
+        ("POST", ["solve", day, part]) => solve(day, Some(part), body),
+        (_, ["days"]) | (_, ["solve", ..]) => Response::error(405, "method not allowed"),
+        _ => Response::error(404, format_args!("no such endpoint {}", path)),
+    }
+}
+
+fn solve(day: &str, part: Option<&str>, body: &[u8]) -> Response {
+    let number: u32 = unwrap_or!(day.parse().ok(),
+        return Response::error(400, format_args!("invalid day '{}'", day)));
+    let day = unwrap_or!(DAYS.iter().find(|day| day.number == number),
+        return Response::error(404, format_args!("day {} hasn't been solved yet", number)));
+    let parts = unwrap_or!(part.map_or(Ok(Parts::Both), str::parse), e =>
+        return Response::error(400, e));
+    let input = unwrap_or!(std::str::from_utf8(body).ok(),
+        return Response::error(400, "the input isn't valid UTF-8"));
+    if input.trim().is_empty() {
+        return Response::error(400, "the input is empty");
+    }
+
+    let (input, normalization) = normalize(input);
+    // A solution that panics on a bad input shouldn't leave the client without
+    // a response
+    let results = unwrap_or!(
+        panic::catch_unwind(AssertUnwindSafe(|| {
+            runner::solve(day, INPUT_NAME, &input, parts, &Answers::default())
+        })),
+        e => return Response::error(500, format_args!("day {} panicked: {}", day.number, panic_message(&*e)))
+    );
+    let diagnostic = results.iter()
+        .find_map(|result| result.answer.as_ref().err()?.downcast_ref::<ParseError>())
+        .map(|e| Diagnostic {
+            line: e.line,
+            column: e.column(),
+            len: e.len,
+            line_text: e.line_text.to_string(),
+            message: e.message.clone(),
+            rendered: e.to_string(),
+        });
+
+    Response::ok(json!({
+        "day": day.number,
+        "normalized": if normalization.is_empty() { None } else { Some(normalization.to_string()) },
+        "parse_error": diagnostic,
+        "results": results.iter().map(|result| result.record()).collect::<Vec<_>>(),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Read;
+    use super::*;
+
+    static INPUT: &str = "1721\n979\n366\n299\n675\n1456\n";
+
+    #[test]
+    fn test_solve() {
+        let response = handle("POST", "/solve/1", INPUT.as_bytes());
+        assert_eq!(response.status, 200);
+        let results = response.body["results"].as_array().unwrap();
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0]["answer"], "514579");
+        assert_eq!(results[1]["answer"], "241861950");
+        assert_eq!(response.body["parse_error"], Value::Null);
+
+        let response = handle("POST", "/solve/1/2", INPUT.replace('\n', "\r\n").as_bytes());
+        assert_eq!(response.body["results"].as_array().unwrap().len(), 1);
+        assert_eq!(response.body["results"][0]["part"], 2);
+        assert_eq!(response.body["normalized"], "6 CRLF line endings converted to LF");
+    }
+
+    #[test]
+    fn test_parse_error() {
+        let response = handle("POST", "/solve/1/1", b"1721\n97x\n");
+        assert_eq!(response.status, 200);
+        let diagnostic = &response.body["parse_error"];
+        assert_eq!((&diagnostic["line"], &diagnostic["column"]), (&json!(2), &json!(1)));
+        assert_eq!(diagnostic["line_text"], "97x");
+        assert!(diagnostic["rendered"].as_str().unwrap().starts_with("request:2:1: invalid number"));
+        assert_eq!(response.body["results"][0]["status"], "fail");
+    }
+
+    #[test]
+    fn test_bad_requests() {
+        assert_eq!(handle("POST", "/solve/30", INPUT.as_bytes()).status, 404);
+        assert_eq!(handle("POST", "/solve/x", INPUT.as_bytes()).status, 400);
+        assert_eq!(handle("POST", "/solve/1/3", INPUT.as_bytes()).status, 400);
+        assert_eq!(handle("POST", "/solve/1", b"").status, 400);
+        assert_eq!(handle("POST", "/solve/1", b"\xff").status, 400);
+        assert_eq!(handle("GET", "/solve/1", b"").status, 405);
+        assert_eq!(handle("GET", "/nothing", b"").status, 404);
+        assert_eq!(handle("GET", "/days", b"").body["days"][0], 1);
+    }
+
+    #[test]
+    fn test_inputs_that_used_to_crash() {
+        let inputs: &[(&str, &[u8])] = &[
+            ("/solve/2/2", b"1-9 a: abc\n"),
+            ("/solve/7/2", b"shiny gold bags contain 1 dark red bag.\ndark red bags contain 1 shiny gold bag.\n"),
+            ("/solve/8", b"jmp -1\n"),
+        ];
+        for &(path, input) in inputs {
+            let response = handle("POST", path, input);
+            assert_eq!(response.status, 200, "{}", path);
+            assert!(response.body["results"][0]["error"].is_string(), "{}", path);
+        }
+
+        // Adding up the accumulator overflows, which only panics in debug builds
+        if cfg!(debug_assertions) {
+            let response = handle("POST", "/solve/8/1", b"acc +2147483647\nacc +1\n");
+            assert_eq!(response.status, 500);
+            assert!(response.body["error"].as_str().unwrap().starts_with("day 8 panicked: "));
+        }
+    }
+
+    #[test]
+    fn test_serve_over_tcp() {
+        let listener = TcpListener::bind(("127.0.0.1", 0)).unwrap();
+        let addr = listener.local_addr().unwrap();
+        thread::spawn(move || serve_on(listener));
+
+        let mut stream = TcpStream::connect(addr).unwrap();
+        write!(stream, "POST /solve/1/1 HTTP/1.1\r\nHost: localhost\r\ncontent-length: {}\r\n\r\n{}",
+            INPUT.len(), INPUT).unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"), "{}", response);
+        let body: Value = serde_json::from_str(response.split("\r\n\r\n").nth(1).unwrap()).unwrap();
+        assert_eq!(body["results"][0]["answer"], "514579");
+    }
+}
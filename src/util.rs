@@ -1,45 +1,1031 @@
-use std::{fmt::Debug, fs, str::FromStr};
-use anyhow::{Context, Error};
+use std::{
+    any::Any,
+    env,
+    fmt::{Debug, Display, Formatter, Result as FmtResult},
+    fs,
+    io::{self, Read},
+    num::ParseIntError,
+    convert::TryFrom,
+    ops::{Index, IndexMut, Range},
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+use anyhow::{Context, Error, Result, bail};
+use itertools::Itertools;
 
-pub(crate) fn get_input(name: &str) -> String {
-    fs::read_to_string(format!("./inputs/{}.txt", name))
-        .with_context(|| format!("no input file with name {}.txt", name))
-        .unwrap()
+/// The environment variable naming an extra directory to search for inputs
+pub static INPUT_DIR_VAR: &str = "AOC2020_INPUT_DIR";
+
+/// The directories searched for input files, in order
+///
+/// That's the directory in [`INPUT_DIR_VAR`], if it's set, then `./inputs`,
+/// then the `inputs` directory of this crate's source, so running from
+/// somewhere else still works.
+pub fn input_dirs() -> Vec<PathBuf> {
+    let mut dirs = Vec::new();
+    if let Some(dir) = env::var_os(INPUT_DIR_VAR) {
+        dirs.push(dir.into());
+    }
+    dirs.push("./inputs".into());
+    dirs.push(Path::new(env!("CARGO_MANIFEST_DIR")).join("inputs"));
+    dirs
+}
+
+/// Finds `file_name` in the first input directory that has it
+pub fn find_input_file(file_name: &str) -> Result<PathBuf> {
+    let tried = input_dirs().into_iter()
+        .map(|dir| dir.join(file_name))
+        .collect_vec();
+    tried.iter().find(|path| path.is_file()).cloned()
+        .with_context(|| format!(
+            "no input file named {}; tried {}",
+            file_name, tried.iter().map(|path| path.display()).join(", ")
+        ))
+}
+
+/// The names of every input for a day whose default input is `name`
+///
+/// That's `name` itself if there's a `name.txt`, then `name/<variant>` for
+/// each `<variant>.txt` in a `name` directory, like `day7/alice` for
+/// `inputs/day7/alice.txt`, in order. Each variant has its own expected answers.
+pub fn input_variants(name: &str) -> Vec<String> {
+    input_variants_in(&input_dirs(), name)
+}
+
+/// [`input_variants`], but searching `dirs`
+pub fn input_variants_in(dirs: &[PathBuf], name: &str) -> Vec<String> {
+    let mut variants: Vec<String> = dirs.iter()
+        .filter_map(|dir| fs::read_dir(dir.join(name)).ok())
+        .flatten()
+        .filter_map(|entry| {
+            let path = entry.ok()?.path();
+            if !path.is_file() || path.extension()? != "txt" { return None; }
+            Some(format!("{}/{}", name, path.file_stem()?.to_str()?))
+        })
+        .collect();
+    variants.sort_unstable();
+    variants.dedup();
+
+    if dirs.iter().any(|dir| dir.join(format!("{}.txt", name)).is_file()) {
+        variants.insert(0, name.into());
+    }
+    variants
+}
+
+/// Reads the input named `name` from the input directories, normalizing it
+pub fn get_input(name: &str) -> Result<String> {
+    get_input_with(name, InputMode::Normalize).map(|(input, _)| input)
+}
+
+/// Reads the input named `name` from the input directories, along with what
+/// had to be normalized in it
+pub fn get_input_with(name: &str, mode: InputMode) -> Result<(String, Normalization)> {
+    let path = find_input_file(&format!("{}.txt", name))?;
+    let input = fs::read_to_string(&path)
+        .with_context(|| format!("failed to read input file {}", path.display()))?;
+    mode.apply(name, input)
+}
+
+/// How an input is checked as it's read
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum InputMode {
+    /// Fix anything [`normalize`] would
+    Normalize,
+    /// Fail if anything would be changed
+    Strict,
+}
+
+impl InputMode {
+    /// Normalizes `input`, named `name`, or checks that it's already normal,
+    /// returning it with what changed
+    pub fn apply(self, name: &str, input: String) -> Result<(String, Normalization)> {
+        let (normalized, changes) = normalize(&input);
+        if changes.is_empty() {
+            return Ok((input, changes));
+        }
+        match self {
+            InputMode::Normalize => Ok((normalized, changes)),
+            InputMode::Strict => bail!("{} isn't normalized: it needs to have {}", name, changes),
+        }
+    }
+}
+
+/// What [`normalize`] changed in an input
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Normalization {
+    /// Whether there was a UTF-8 byte order mark at the start
+    pub bom: bool,
+    /// The number of `\r\n` line endings turned into `\n`
+    pub crlf: usize,
+    /// The number of lines with trailing whitespace trimmed
+    pub trailing_whitespace: usize,
+    /// The number of blank lines removed from the end
+    pub trailing_blank_lines: usize,
+    /// Whether a final newline was added
+    pub final_newline: bool,
+}
+
+impl Normalization {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+impl Display for Normalization {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        let mut changes = Vec::new();
+        if self.bom {
+            changes.push("the byte order mark removed".to_owned());
+        }
+        let plural = |n: usize, noun: &str| format!("{} {}{}", n, noun, if n == 1 { "" } else { "s" });
+        if self.crlf > 0 {
+            changes.push(format!("{} converted to LF", plural(self.crlf, "CRLF line ending")));
+        }
+        if self.trailing_whitespace > 0 {
+            changes.push(format!("{} with trailing whitespace trimmed",
+                plural(self.trailing_whitespace, "line")));
+        }
+        if self.trailing_blank_lines > 0 {
+            changes.push(format!("{} removed from the end",
+                plural(self.trailing_blank_lines, "blank line")));
+        }
+        if self.final_newline {
+            changes.push("a final newline added".to_owned());
+        }
+        write!(f, "{}", changes.join(", "))
+    }
+}
+
+/// Cleans up things editors and other machines add to inputs, which the
+/// parsers don't expect
+///
+/// That's a byte order mark, `\r\n` line endings, trailing whitespace on
+/// lines and blank lines at the end. Non-empty inputs end with one newline.
+pub fn normalize(input: &str) -> (String, Normalization) {
+    let mut changes = Normalization::default();
+    let input = match input.strip_prefix('\u{feff}') {
+        Some(input) => {
+            changes.bom = true;
+            input
+        }
+        None => input,
+    };
+
+    let mut normalized = String::with_capacity(input.len());
+    for line in input.split_inclusive('\n') {
+        let line = match line.strip_suffix("\r\n") {
+            Some(line) => {
+                changes.crlf += 1;
+                line
+            }
+            None => line.strip_suffix('\n').unwrap_or(line),
+        };
+        let trimmed = line.trim_end();
+        if trimmed.len() != line.len() {
+            changes.trailing_whitespace += 1;
+        }
+        normalized.push_str(trimmed);
+        normalized.push('\n');
+    }
+
+    let content_len = normalized.trim_end_matches('\n').len();
+    // Every line ends in a newline now, so they're all blank lines but the one
+    // ending the last line with something on it, if there is one
+    let newlines = normalized.len() - content_len;
+    changes.trailing_blank_lines = if content_len == 0 { newlines } else { newlines - 1 };
+    normalized.truncate(content_len);
+    if !normalized.is_empty() {
+        changes.final_newline = changes.trailing_blank_lines == 0 && !input.ends_with('\n');
+        normalized.push('\n');
+    }
+    (normalized, changes)
+}
+
+/// Where to read a puzzle input from
+#[derive(Clone, Debug, PartialEq)]
+pub enum InputSource {
+    /// The input with the day's name, found by [`get_input`]
+    Default,
+    /// A specific file
+    File(PathBuf),
+    /// Standard input
+    Stdin,
+}
+
+impl InputSource {
+    /// The name to give the input in messages, where `name` is the default source's
+    pub fn name(&self, name: &str) -> String {
+        match self {
+            InputSource::Default => name.into(),
+            InputSource::File(path) => path.display().to_string(),
+            InputSource::Stdin => "<stdin>".into(),
+        }
+    }
+
+    /// Reads the input, using `name` if this is the default source, along with
+    /// what had to be normalized in it
+    pub fn read(&self, name: &str, mode: InputMode) -> Result<(String, Normalization)> {
+        let input = match self {
+            InputSource::Default => return get_input_with(name, mode),
+            InputSource::File(path) => fs::read_to_string(path)
+                .with_context(|| format!("failed to read input file {}", path.display()))?,
+            InputSource::Stdin => {
+                let mut input = String::new();
+                io::stdin().read_to_string(&mut input)
+                    .context("failed to read input from stdin")?;
+                input
+            }
+        };
+        mode.apply(&self.name(name), input)
+    }
+}
+
+impl FromStr for InputSource {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "" => bail!("empty input path"),
+            "-" => InputSource::Stdin,
+            _ => InputSource::File(s.into()),
+        })
+    }
 }
 
 /// Converts an error to an `anyhow`-compatible error using its Debug impl.
 ///
 /// Useful for "errors" that don't actually implement `std::error::Error`, since
 /// they can't be converted to `anyhow::Error` as easily.
-pub(crate) fn error_from_debug(e: impl Debug) -> Error {
+pub fn error_from_debug(e: impl Debug) -> Error {
     Error::msg(format!("{:?}", e))
 }
 
+/// The message a panic was started with, from the payload `catch_unwind` gives
+pub fn panic_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "panicked".to_owned()
+    }
+}
+
+/// A solution to one day's puzzle, split into a parsing stage and the two parts
+pub trait Solution {
+    /// The puzzle input, once parsed; it may borrow from the raw input
+    type Input<'a>;
+    /// The type of both parts' answers
+    type Answer: Display;
+
+    fn parse(input: &str) -> Result<Self::Input<'_>, ParseError>;
+    fn part1(input: &Self::Input<'_>) -> Result<Self::Answer>;
+    fn part2(input: &Self::Input<'_>) -> Result<Self::Answer>;
+
+    /// Generates a random valid input with roughly `size` records (lines,
+    /// passports, groups, etc.), for stress testing
+    fn generate(rng: &mut Rng, size: usize) -> String;
+}
+
+/// A parsed puzzle input, with its solution's type erased
+///
+/// This lets days with different input and answer types be treated the same.
+pub trait Parsed {
+    fn part1(&self) -> Result<String>;
+    fn part2(&self) -> Result<String>;
+
+    fn solve(&self, part: Part) -> Result<String> {
+        match part {
+            Part::One => self.part1(),
+            Part::Two => self.part2(),
+        }
+    }
+}
+
+struct ParsedInput<'a, S: Solution>(S::Input<'a>);
+
+impl<S: Solution> Parsed for ParsedInput<'_, S> {
+    fn part1(&self) -> Result<String> {
+        S::part1(&self.0).map(|answer| answer.to_string())
+    }
+
+    fn part2(&self) -> Result<String> {
+        S::part2(&self.0).map(|answer| answer.to_string())
+    }
+}
+
+/// Parses `input` for solution `S`, erasing its types
+pub fn parse_erased<S: Solution + 'static>(input: &str) -> Result<Box<dyn Parsed + '_>, ParseError> {
+    S::parse(input).map(|input| Box::new(ParsedInput::<S>(input)) as Box<dyn Parsed>)
+}
+
+/// One part of a day's puzzle
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Part {
+    One,
+    Two,
+}
+
+impl Part {
+    pub fn number(self) -> u8 {
+        match self {
+            Part::One => 1,
+            Part::Two => 2,
+        }
+    }
+}
+
+impl Display for Part {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        write!(f, "{}", self.number())
+    }
+}
+
+impl FromStr for Part {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "1" => Part::One,
+            "2" => Part::Two,
+            _ => bail!("expected part 1 or 2 but found '{}'", s),
+        })
+    }
+}
+
+/// Which parts of a day's puzzle to solve
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Parts {
+    One,
+    Two,
+    Both,
+}
+
+impl Parts {
+    pub fn iter(self) -> impl Iterator<Item = Part> {
+        let parts: &[Part] = match self {
+            Parts::One => &[Part::One],
+            Parts::Two => &[Part::Two],
+            Parts::Both => &[Part::One, Part::Two],
+        };
+        parts.iter().copied()
+    }
+}
+
+impl FromStr for Parts {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.parse().map(|part| match part {
+            Part::One => Parts::One,
+            Part::Two => Parts::Two,
+        })
+    }
+}
+
+/// Prints `rows` as a table under `headers`, with each column padded to fit
+pub fn print_table<R: AsRef<[String]>>(headers: &[&str], rows: &[R]) {
+    let widths = (0..headers.len())
+        .map(|col| rows.iter()
+            .map(|row| row.as_ref()[col].len())
+            .chain(std::iter::once(headers[col].len()))
+            .max()
+            .unwrap_or_default())
+        .collect_vec();
+
+    fn format_row<S: AsRef<str>>(cells: impl IntoIterator<Item = S>, widths: &[usize]) -> String {
+        let line = cells.into_iter().zip(widths)
+            .map(|(cell, &width)| format!("{:<width$}", cell.as_ref(), width = width))
+            .join("  ");
+        line.trim_end().to_owned()
+    }
+
+    println!("{}", format_row(headers, &widths));
+    println!("{}", format_row(widths.iter().map(|&width| "-".repeat(width)), &widths));
+    for row in rows {
+        println!("{}", format_row(row.as_ref(), &widths));
+    }
+}
+
+/// An error in parsing an input, with where in the input it happened
+///
+/// Its `Display` impl prints the offending line with a caret under the span,
+/// like a compiler error.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ParseError {
+    /// The name of the input, if known
+    pub input: Option<Box<str>>,
+    /// The 1-based line number
+    pub line: usize,
+    /// The byte offset of the span within the line
+    pub start: usize,
+    /// The length of the span in bytes, clipped to the end of the line
+    pub len: usize,
+    /// The whole line the error is on
+    pub line_text: Box<str>,
+    pub message: String,
+}
+
+impl ParseError {
+    /// Creates an error about `span`, which should be a slice of `src`
+    ///
+    /// If it isn't, the error is about the whole of `src`.
+    pub fn new(src: &str, span: &str, message: impl Display) -> Self {
+        let src_start = src.as_ptr() as usize;
+        let span_start = span.as_ptr() as usize;
+        let (offset, span_len) =
+            if span_start >= src_start && span_start + span.len() <= src_start + src.len() {
+                (span_start - src_start, span.len())
+            } else {
+                (0, src.len())
+            };
+
+        let line_start = src[..offset].rfind('\n').map_or(0, |i| i + 1);
+        let line_end = src[offset..].find('\n').map_or(src.len(), |i| offset + i);
+        let line_text = src[line_start..line_end].strip_suffix('\r')
+            .unwrap_or(&src[line_start..line_end]);
+        let start = offset - line_start;
+
+        Self {
+            input: None,
+            line: src[..offset].matches('\n').count() + 1,
+            start,
+            len: span_len.min(line_text.len().saturating_sub(start)),
+            line_text: line_text.into(),
+            message: message.to_string(),
+        }
+    }
+
+    /// Moves this error, which came from parsing `inner`, to where `inner` is in `outer`
+    ///
+    /// `inner` should be a slice of `outer`.
+    pub fn within(self, outer: &str, inner: &str) -> Self {
+        let line_offset: usize = inner.split('\n').take(self.line - 1)
+            .map(|line| line.len() + 1)
+            .sum();
+        let start = (line_offset + self.start).min(inner.len());
+        let end = (start + self.len).min(inner.len());
+        Self {
+            input: self.input.clone(),
+            ..Self::new(outer, &inner[start..end], self.message)
+        }
+    }
+
+    /// Names the input this error is in
+    pub fn with_input(self, input: &str) -> Self {
+        Self { input: Some(input.into()), ..self }
+    }
+
+    /// The 1-based column the span starts at, counted in chars
+    pub fn column(&self) -> usize {
+        self.line_text[..self.start].chars().count() + 1
+    }
+
+    /// Where the error is, like `day7:3:5`
+    pub fn location(&self) -> String {
+        match &self.input {
+            Some(input) => format!("{}:{}:{}", input, self.line, self.column()),
+            None => format!("line {}, column {}", self.line, self.column()),
+        }
+    }
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        let line_num = self.line.to_string();
+        let gutter = " ".repeat(line_num.len());
+        let carets = self.line_text[self.start..self.start + self.len].chars().count().max(1);
+
+        writeln!(f, "{}: {}", self.location(), self.message)?;
+        writeln!(f, "{} |", gutter)?;
+        writeln!(f, "{} | {}", line_num, self.line_text)?;
+        write!(
+            f, "{} | {}{}",
+            gutter, " ".repeat(self.column() - 1), "^".repeat(carets)
+        )
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// Converts errors from parsing a slice of some input into [`ParseError`]s
+pub trait IntoParseError {
+    /// Converts this error, which came from parsing `span`, a slice of `src`
+    fn into_parse_error(self, src: &str, span: &str) -> ParseError;
+}
+
+impl IntoParseError for ParseError {
+    fn into_parse_error(self, src: &str, span: &str) -> ParseError {
+        self.within(src, span)
+    }
+}
+
+impl IntoParseError for ParseIntError {
+    fn into_parse_error(self, src: &str, span: &str) -> ParseError {
+        ParseError::new(src, span, format_args!("invalid number: {}", self))
+    }
+}
+
+/// Adds a location to a failure, like `anyhow::Context` does with a message
+pub trait ParseContext<T> {
+    /// Turns a failure into a [`ParseError`] about `span`, a slice of `src`
+    fn parse_context(self, src: &str, span: &str, message: impl Display) -> Result<T, ParseError>;
+}
+
+impl<T> ParseContext<T> for Option<T> {
+    fn parse_context(self, src: &str, span: &str, message: impl Display) -> Result<T, ParseError> {
+        self.ok_or_else(|| ParseError::new(src, span, message))
+    }
+}
+
+impl<T, E: Display> ParseContext<T> for Result<T, E> {
+    fn parse_context(self, src: &str, span: &str, message: impl Display) -> Result<T, ParseError> {
+        self.map_err(|e| ParseError::new(src, span, format_args!("{}: {}", message, e)))
+    }
+}
+
 // TODO: I *think* this won't break past days' solutions
-pub(crate) trait ParseStrLines<Coll, E> {
+pub trait ParseStrLines<Coll, E> {
     fn parse_lines(self) -> Result<Coll, E>;
 }
 
-impl<T: FromStr> ParseStrLines<Vec<T>, T::Err> for &str {
-    fn parse_lines(self) -> Result<Vec<T>, T::Err> {
+impl<T: FromStr> ParseStrLines<Vec<T>, ParseError> for &str
+where
+    T::Err: IntoParseError,
+{
+    fn parse_lines(self) -> Result<Vec<T>, ParseError> {
         self
             .lines()
-            .map(str::parse)
+            .map(|line| line.parse().map_err(|e: T::Err| e.into_parse_error(self, line)))
             .collect()
     }
 }
 
-impl<T: FromStr> ParseStrLines<Array<T>, T::Err> for &str {
-    fn parse_lines(self) -> Result<Array<T>, T::Err> {
+impl<T: FromStr> ParseStrLines<Array<T>, ParseError> for &str
+where
+    T::Err: IntoParseError,
+{
+    fn parse_lines(self) -> Result<Array<T>, ParseError> {
         self
             .lines()
-            .map(str::parse)
+            .map(|line| line.parse().map_err(|e: T::Err| e.into_parse_error(self, line)))
             .collect()
     }
 }
 
+/// A small parser that works through `src` a piece at a time
+///
+/// Each method consumes something from the front of what's left, and errors
+/// point at where the parser was when it failed, so they're located in `src`.
+/// Use [`Parser::parse`] to parse a whole string with a closure built from
+/// these.
+#[derive(Copy, Clone, Debug)]
+pub struct Parser<'a> {
+    src: &'a str,
+    rest: &'a str,
+}
+
+impl<'a> Parser<'a> {
+    pub fn new(src: &'a str) -> Self {
+        Self { src, rest: src }
+    }
+
+    /// Parses all of `src` with `f`, failing if anything is left over
+    pub fn parse<T>(
+        src: &'a str, f: impl FnOnce(&mut Self) -> Result<T, ParseError>
+    ) -> Result<T, ParseError> {
+        let mut parser = Self::new(src);
+        let parsed = f(&mut parser)?;
+        parser.end()?;
+        Ok(parsed)
+    }
+
+    /// Consumes everything that's left
+    pub fn rest(&mut self) -> &'a str {
+        self.take(self.rest.len())
+    }
+
+    /// An error about `span`, which should be a slice of `src`
+    pub fn error(&self, span: &str, message: impl Display) -> ParseError {
+        ParseError::new(self.src, span, message)
+    }
+
+    /// An error about what's next, up to the next whitespace
+    fn error_here(&self, message: impl Display) -> ParseError {
+        let len = match self.rest.find(char::is_whitespace) {
+            Some(0) => self.rest.chars().next().map_or(0, char::len_utf8),
+            Some(len) => len,
+            None => self.rest.len(),
+        };
+        self.error(&self.rest[..len], message)
+    }
+
+    /// Consumes the first `len` bytes of what's left
+    fn take(&mut self, len: usize) -> &'a str {
+        let (taken, rest) = self.rest.split_at(len);
+        self.rest = rest;
+        taken
+    }
+
+    /// Consumes `literal`
+    pub fn literal(&mut self, literal: &str) -> Result<&'a str, ParseError> {
+        if self.rest.starts_with(literal) {
+            Ok(self.take(literal.len()))
+        } else {
+            Err(self.error_here(format_args!("expected {:?}", literal)))
+        }
+    }
+
+    /// Consumes an integer, with an optional sign
+    pub fn int<T>(&mut self) -> Result<T, ParseError>
+    where
+        T: FromStr<Err = ParseIntError>,
+    {
+        let sign_len = if self.rest.starts_with(&['+', '-'][..]) { 1 } else { 0 };
+        let digits_len = self.rest[sign_len..]
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(self.rest.len() - sign_len);
+        if digits_len == 0 {
+            return Err(self.error_here("expected a number"));
+        }
+
+        let int_str = self.take(sign_len + digits_len);
+        int_str.parse().map_err(|e| self.error(int_str, format_args!("invalid number: {}", e)))
+    }
+
+    /// Consumes one char
+    pub fn char(&mut self) -> Result<char, ParseError> {
+        let c = self.rest.chars().next()
+            .ok_or_else(|| self.error_here("expected a char"))?;
+        self.take(c.len_utf8());
+        Ok(c)
+    }
+
+    /// Consumes a word made of letters and digits
+    pub fn word(&mut self) -> Result<&'a str, ParseError> {
+        let len = self.rest
+            .find(|c: char| !c.is_alphanumeric())
+            .unwrap_or(self.rest.len());
+        if len == 0 {
+            return Err(self.error_here("expected a word"));
+        }
+        Ok(self.take(len))
+    }
+
+    /// Consumes everything up to the next `literal`, but not `literal` itself
+    pub fn until(&mut self, literal: &str) -> Result<&'a str, ParseError> {
+        let len = self.rest.find(literal)
+            .ok_or_else(|| self.error(self.rest, format_args!("expected {:?}", literal)))?;
+        Ok(self.take(len))
+    }
+
+    /// Tries `f`, going back to where it started if it fails
+    pub fn optional<T>(&mut self, f: impl FnOnce(&mut Self) -> Result<T, ParseError>) -> Option<T> {
+        let start = *self;
+        f(self).map_err(|_| *self = start).ok()
+    }
+
+    /// Consumes one or more of `item`, separated by `separator`
+    pub fn separated<T>(
+        &mut self, separator: &str, mut item: impl FnMut(&mut Self) -> Result<T, ParseError>
+    ) -> Result<Vec<T>, ParseError> {
+        let mut items = vec![item(self)?];
+        while self.optional(|p| p.literal(separator)).is_some() {
+            items.push(item(self)?);
+        }
+        Ok(items)
+    }
+
+    /// Fails if there's anything left
+    pub fn end(&self) -> Result<(), ParseError> {
+        if self.rest.is_empty() {
+            Ok(())
+        } else {
+            Err(self.error(self.rest, format_args!("unexpected {:?}", self.rest)))
+        }
+    }
+}
+
+/// Splits input into records separated by blank lines, like day 4's passports
+pub trait SplitRecords {
+    /// Each record, with the 1-based line number it starts on
+    ///
+    /// Lines with only whitespace count as blank, any number of them can
+    /// separate records, and both `\n` and `\r\n` line endings work. Records
+    /// are slices of the input without their final line ending, so they can
+    /// be used with [`ParseError::within`].
+    fn records(&self) -> Records<'_>;
+}
+
+impl SplitRecords for str {
+    fn records(&self) -> Records<'_> {
+        Records { src: self, pos: 0, line: 1 }
+    }
+}
+
+/// The iterator returned by [`SplitRecords::records`]
+pub struct Records<'a> {
+    src: &'a str,
+    /// The byte offset of the start of the next line
+    pos: usize,
+    /// The line number of the next line
+    line: usize,
+}
+
+impl<'a> Records<'a> {
+    /// The next line's byte offset, and the line without its line ending
+    fn next_line(&mut self) -> Option<(usize, &'a str)> {
+        let offset = self.pos;
+        let rest = &self.src[offset..];
+        if rest.is_empty() { return None; }
+
+        let len = rest.find('\n').map_or(rest.len(), |i| i + 1);
+        self.pos += len;
+        self.line += 1;
+        let line = &rest[..len];
+        let line = line.strip_suffix('\n').unwrap_or(line);
+        Some((offset, line.strip_suffix('\r').unwrap_or(line)))
+    }
+}
+
+impl<'a> Iterator for Records<'a> {
+    type Item = (usize, &'a str);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (start, start_line, mut end) = loop {
+            let line = self.line;
+            let (offset, text) = self.next_line()?;
+            if !text.trim().is_empty() {
+                break (offset, line, offset + text.len());
+            }
+        };
+        while let Some((offset, text)) = self.next_line() {
+            if text.trim().is_empty() { break; }
+            end = offset + text.len();
+        }
+        Some((start_line, &self.src[start..end]))
+    }
+}
+
+/// How a [`Grid`] treats positions past its edges
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Edges {
+    /// There's nothing outside the grid
+    Bounded,
+    /// The grid repeats to the left and right forever, like day 3's map, but
+    /// there's nothing above or below it
+    Wrapping,
+    /// The grid repeats in every direction
+    Toroidal,
+}
+
+/// A rectangular grid of cells, parsed one char per cell
+///
+/// Positions are `(x, y)`, with `(0, 0)` in the top left. `Display` prints
+/// each cell's `Display` with a newline between rows, so a grid of cells
+/// that display as their char prints back out as it was parsed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Grid<T> {
+    cells: Box<[T]>,
+    width: usize,
+    height: usize,
+    edges: Edges,
+}
+
+impl<T> Grid<T> {
+    /// The offsets of the 4 orthogonal neighbours
+    const NEIGHBOURS_4: [(isize, isize); 4] = [(0, -1), (-1, 0), (1, 0), (0, 1)];
+    /// The offsets of the 8 neighbours, including diagonals
+    const NEIGHBOURS_8: [(isize, isize); 8] = [
+        (-1, -1), (0, -1), (1, -1),
+        (-1, 0), (1, 0),
+        (-1, 1), (0, 1), (1, 1),
+    ];
+
+    /// Parses each line of `s` as a row, using `parse_cell` on each char
+    ///
+    /// The grid is bounded; see [`Grid::with_edges`].
+    pub fn parse_with<E: Display>(
+        s: &str, mut parse_cell: impl FnMut(char) -> Result<T, E>
+    ) -> Result<Self, ParseError> {
+        let mut cells = Vec::new();
+        let mut width = None;
+        let mut height = 0;
+        for line in s.lines() {
+            let mut row_len = 0;
+            for (i, c) in line.char_indices() {
+                cells.push(parse_cell(c)
+                    .map_err(|e| ParseError::new(s, &line[i..i + c.len_utf8()], e))?);
+                row_len += 1;
+            }
+            match width {
+                None => width = Some(row_len),
+                Some(width) if width != row_len => return Err(ParseError::new(s, line, format_args!(
+                    "expected {} cells like the first row but found {}", width, row_len
+                ))),
+                Some(_) => {}
+            }
+            height += 1;
+        }
+
+        Ok(Self {
+            cells: cells.into(),
+            width: width.unwrap_or(0),
+            height,
+            edges: Edges::Bounded,
+        })
+    }
+
+    /// Changes how positions past the edges are treated
+    pub fn with_edges(self, edges: Edges) -> Self {
+        Self { edges, ..self }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn edges(&self) -> Edges {
+        self.edges
+    }
+
+    /// Finds the cell in the grid that `(x, y)` refers to, wrapping it
+    /// around if the edges allow it
+    pub fn resolve(&self, x: isize, y: isize) -> Option<(usize, usize)> {
+        fn wrap(n: isize, len: usize) -> Option<usize> {
+            if len == 0 { None } else { Some(n.rem_euclid(len as isize) as usize) }
+        }
+        fn bound(n: isize, len: usize) -> Option<usize> {
+            usize::try_from(n).ok().filter(|&n| n < len)
+        }
+
+        match self.edges {
+            Edges::Bounded => Some((bound(x, self.width)?, bound(y, self.height)?)),
+            Edges::Wrapping => Some((wrap(x, self.width)?, bound(y, self.height)?)),
+            Edges::Toroidal => Some((wrap(x, self.width)?, wrap(y, self.height)?)),
+        }
+    }
+
+    pub fn get(&self, x: isize, y: isize) -> Option<&T> {
+        let (x, y) = self.resolve(x, y)?;
+        Some(&self.cells[y * self.width + x])
+    }
+
+    pub fn get_mut(&mut self, x: isize, y: isize) -> Option<&mut T> {
+        let (x, y) = self.resolve(x, y)?;
+        Some(&mut self.cells[y * self.width + x])
+    }
+
+    /// The positions and cells of the 4 orthogonal neighbours of `(x, y)`
+    /// that exist, each cell once
+    pub fn neighbours_4(&self, x: usize, y: usize)
+        -> impl Iterator<Item = ((usize, usize), &T)> + '_
+    {
+        self.neighbours(x, y, &Self::NEIGHBOURS_4)
+    }
+
+    /// The positions and cells of the 8 neighbours of `(x, y)`, including
+    /// diagonals, that exist, each cell once
+    pub fn neighbours_8(&self, x: usize, y: usize)
+        -> impl Iterator<Item = ((usize, usize), &T)> + '_
+    {
+        self.neighbours(x, y, &Self::NEIGHBOURS_8)
+    }
+
+    /// On grids less than 3 wide or high, wrapping can bring several offsets
+    /// to the same cell, or back to `(x, y)` itself; those are skipped
+    fn neighbours<'a>(&'a self, x: usize, y: usize, offsets: &'static [(isize, isize)])
+        -> impl Iterator<Item = ((usize, usize), &'a T)> + 'a
+    {
+        let resolve = move |&(dx, dy): &(isize, isize)| {
+            self.resolve(x as isize + dx, y as isize + dy)
+        };
+        offsets.iter().enumerate()
+            .filter_map(move |(i, offset)| {
+                let pos = resolve(offset).filter(|&pos| pos != (x, y))?;
+                let seen = offsets[..i].iter().any(|earlier| resolve(earlier) == Some(pos));
+                if seen { None } else { Some((pos, &self[pos])) }
+            })
+    }
+
+    /// Every row, top to bottom
+    pub fn rows(&self) -> impl Iterator<Item = &[T]> + '_ {
+        // `chunks` panics on 0, but then there are no cells anyway
+        self.cells.chunks(self.width.max(1))
+    }
+
+    /// The cells in column `x`, top to bottom
+    pub fn column(&self, x: usize) -> impl Iterator<Item = &T> + '_ {
+        assert!(x < self.width, "column {} out of bounds", x);
+        self.cells.iter().skip(x).step_by(self.width)
+    }
+
+    /// Every column, left to right
+    pub fn columns(&self) -> impl Iterator<Item = impl Iterator<Item = &T> + '_> + '_ {
+        (0..self.width).map(move |x| self.column(x))
+    }
+
+    /// Every position and its cell, row by row
+    pub fn iter(&self) -> impl Iterator<Item = ((usize, usize), &T)> + '_ {
+        self.cells.iter().enumerate()
+            .map(move |(i, cell)| ((i % self.width, i / self.width), cell))
+    }
+}
+
+impl<T> Index<(usize, usize)> for Grid<T> {
+    type Output = T;
+
+    /// Panics if `(x, y)` doesn't exist, after wrapping around
+    fn index(&self, (x, y): (usize, usize)) -> &T {
+        self.get(x as isize, y as isize)
+            .unwrap_or_else(|| panic!("({}, {}) is outside the grid", x, y))
+    }
+}
+
+impl<T> IndexMut<(usize, usize)> for Grid<T> {
+    fn index_mut(&mut self, (x, y): (usize, usize)) -> &mut T {
+        self.get_mut(x as isize, y as isize)
+            .unwrap_or_else(|| panic!("({}, {}) is outside the grid", x, y))
+    }
+}
+
+impl<T: TryFrom<char>> FromStr for Grid<T>
+where
+    T::Error: Display,
+{
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse_with(s, T::try_from)
+    }
+}
+
+impl<T: Display> Display for Grid<T> {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        for (y, row) in self.rows().enumerate() {
+            if y > 0 { writeln!(f)?; }
+            for cell in row {
+                cell.fmt(f)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A small seeded pseudo-random number generator (SplitMix64)
+///
+/// It's here instead of a dependency so a seed gives the same generated input
+/// on every platform and version.
+#[derive(Clone, Debug)]
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Self(seed)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// A number in `range`, which must not be empty
+    pub fn range(&mut self, range: Range<i64>) -> i64 {
+        assert!(range.start < range.end, "empty range");
+        let len = range.end.wrapping_sub(range.start) as u64;
+        range.start.wrapping_add((self.next_u64() % len) as i64)
+    }
+
+    /// An index into a collection of length `len`, which must not be 0
+    pub fn index(&mut self, len: usize) -> usize {
+        self.range(0..len as i64) as usize
+    }
+
+    /// True with probability `p`
+    pub fn chance(&mut self, p: f64) -> bool {
+        (self.next_u64() >> 11) as f64 / ((1u64 << 53) as f64) < p
+    }
+
+    pub fn choose<'a, T>(&mut self, items: &'a [T]) -> &'a T {
+        &items[self.index(items.len())]
+    }
+
+    pub fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            items.swap(i, self.index(i + 1));
+        }
+    }
+}
+
 // I always forget the brackets
-pub(crate) type Array<T> = Box<[T]>;
+pub type Array<T> = Box<[T]>;
 
 macro_rules! unwrap_or {
     ($opt:expr, $default:expr) => {
@@ -53,3 +1039,201 @@ macro_rules! unwrap_or {
         }
     };
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_input_source() {
+        assert_eq!("-".parse::<InputSource>().unwrap(), InputSource::Stdin);
+        assert_eq!(
+            "inputs/day1.txt".parse::<InputSource>().unwrap(),
+            InputSource::File("inputs/day1.txt".into())
+        );
+        assert!("".parse::<InputSource>().is_err());
+    }
+
+    #[test]
+    fn test_normalize() {
+        let (input, changes) = normalize("\u{feff}a \r\nb\r\n\r\n  \n\n");
+        assert_eq!(input, "a\nb\n");
+        assert_eq!(changes, Normalization {
+            bom: true,
+            crlf: 3,
+            trailing_whitespace: 2,
+            trailing_blank_lines: 3,
+            final_newline: false,
+        });
+        assert_eq!(
+            changes.to_string(),
+            "the byte order mark removed, 3 CRLF line endings converted to LF, \
+             2 lines with trailing whitespace trimmed, 3 blank lines removed from the end"
+        );
+
+        let (input, changes) = normalize("a\n\nb");
+        assert_eq!(input, "a\n\nb\n");
+        assert_eq!(changes.to_string(), "a final newline added");
+
+        assert!(normalize("a\n\nb\n").1.is_empty());
+        assert_eq!(normalize(""), (String::new(), Normalization::default()));
+        let (input, changes) = normalize("\n \n");
+        assert_eq!(input, "");
+        assert_eq!(changes.to_string(), "1 line with trailing whitespace trimmed, 2 blank lines removed from the end");
+    }
+
+    #[test]
+    fn test_input_mode() {
+        let (input, changes) = InputMode::Normalize.apply("x", "a\r\n".into()).unwrap();
+        assert_eq!((input.as_str(), changes.crlf), ("a\n", 1));
+        assert_eq!(InputMode::Strict.apply("x", "a\n".into()).unwrap(), ("a\n".into(), Normalization::default()));
+        let e = InputMode::Strict.apply("x", "a\r\n".into()).unwrap_err();
+        assert_eq!(e.to_string(), "x isn't normalized: it needs to have 1 CRLF line ending converted to LF");
+    }
+
+    #[test]
+    fn test_parse_error_location() {
+        let src = "first line\nsecond line\nthird";
+        let e = ParseError::new(src, &src[18..22], "bad word");
+        assert_eq!((e.line, e.column(), e.len), (2, 8, 4));
+        assert_eq!(&*e.line_text, "second line");
+        assert_eq!(e.with_input("day0").to_string(), "\
+day0:2:8: bad word
+  |
+2 | second line
+  |        ^^^^");
+    }
+
+    #[test]
+    fn test_parse_error_within() {
+        let outer = "a\nb: 12x4";
+        let inner = &outer[5..];
+        let e = inner.parse::<u32>().unwrap_err().into_parse_error(inner, &inner[1..3]);
+        let e = e.within(outer, inner);
+        assert_eq!((e.line, e.column(), e.len), (2, 5, 2));
+    }
+
+    #[test]
+    fn test_parse_lines_reports_line() {
+        let parsed: Result<Vec<u32>, _> = "1\n2\nthree\n4".parse_lines();
+        let e = parsed.unwrap_err();
+        assert_eq!((e.line, e.column(), e.len), (3, 1, 5));
+    }
+
+    #[test]
+    fn test_parser() {
+        let parsed = Parser::parse("move 3 from 12, -4, +5; done", |p| {
+            let verb = p.word()?;
+            p.literal(" ")?;
+            let count: u32 = p.int()?;
+            p.literal(" from ")?;
+            let from: Vec<i32> = p.separated(", ", Parser::int)?;
+            let note = p.optional(|p| p.literal(" (note)"));
+            p.literal("; ")?;
+            Ok((verb, count, from, note, p.rest()))
+        }).unwrap();
+        assert_eq!(parsed, ("move", 3, vec![12, -4, 5], None, "done"));
+    }
+
+    #[test]
+    fn test_parser_errors() {
+        let e = Parser::parse("1-x", |p| { p.int::<u8>()?; p.literal("-")?; p.int::<u8>() })
+            .unwrap_err();
+        assert_eq!((e.column(), e.len, &*e.message), (3, 1, "expected a number"));
+
+        let e = Parser::parse("1, 2, 300", |p| p.separated(", ", Parser::int::<u8>)).unwrap_err();
+        assert_eq!((e.column(), e.len), (7, 3));
+        assert!(e.message.starts_with("invalid number"), "{}", e.message);
+
+        let e = Parser::parse("ab cd", Parser::word).unwrap_err();
+        assert_eq!((e.column(), e.len, &*e.message), (3, 3, "unexpected \" cd\""));
+
+        let e = Parser::parse("abc", |p| p.until(":")).unwrap_err();
+        assert_eq!((e.column(), e.len, &*e.message), (1, 3, "expected \":\""));
+    }
+
+    #[test]
+    fn test_records() {
+        let input = "a b\nc\n\nd\n \t\n\n\ne\nf\n\n";
+        assert_eq!(input.records().collect_vec(), [(1, "a b\nc"), (4, "d"), (8, "e\nf")]);
+
+        let input = "\r\na\r\nb\r\n\r\nc";
+        assert_eq!(input.records().collect_vec(), [(2, "a\r\nb"), (5, "c")]);
+
+        assert_eq!("".records().count(), 0);
+        assert_eq!("\n  \n".records().count(), 0);
+    }
+
+    #[test]
+    fn test_grid() {
+        let grid = Grid::parse_with("123\n456", |c| c.to_digit(10).ok_or("not a digit")).unwrap();
+        assert_eq!((grid.width(), grid.height()), (3, 2));
+        assert_eq!(grid[(2, 1)], 6);
+        assert_eq!(grid.get(3, 0), None);
+        assert_eq!(grid.rows().collect_vec(), [&[1, 2, 3][..], &[4, 5, 6][..]]);
+        assert_eq!(grid.column(1).copied().collect_vec(), [2, 5]);
+        assert_eq!(grid.columns().count(), 3);
+        assert_eq!(grid.to_string(), "123\n456");
+
+        let neighbours = |grid: &Grid<u32>, n: usize| {
+            let found = if n == 4 {
+                grid.neighbours_4(0, 0).map(|(_, &cell)| cell).collect_vec()
+            } else {
+                grid.neighbours_8(0, 0).map(|(_, &cell)| cell).collect_vec()
+            };
+            found.into_iter().sorted().collect_vec()
+        };
+        assert_eq!(neighbours(&grid, 4), [2, 4]);
+        assert_eq!(neighbours(&grid, 8), [2, 4, 5]);
+
+        let grid = grid.with_edges(Edges::Wrapping);
+        assert_eq!(grid[(4, 0)], 2);
+        assert_eq!(grid.get(-1, 1), Some(&6));
+        assert_eq!(grid.get(0, 2), None);
+        assert_eq!(neighbours(&grid, 4), [2, 3, 4]);
+
+        let grid = grid.with_edges(Edges::Toroidal);
+        assert_eq!(grid.get(-1, -1), Some(&6));
+        assert_eq!(neighbours(&grid, 8), [2, 3, 4, 5, 6]);
+
+        let grid = Grid::parse_with("1", |c| c.to_digit(10).ok_or("not a digit")).unwrap()
+            .with_edges(Edges::Toroidal);
+        assert_eq!(neighbours(&grid, 8), Vec::<u32>::new());
+    }
+
+    #[test]
+    fn test_grid_errors() {
+        let e = "ab\nabc".parse::<Grid<char>>().unwrap_err();
+        assert_eq!((e.line, e.column()), (2, 1));
+
+        let e = Grid::parse_with("..\n.x", |c| if c == '.' { Ok(()) } else { Err("bad cell") })
+            .unwrap_err();
+        assert_eq!((e.line, e.column(), e.len), (2, 2, 1));
+    }
+
+    #[test]
+    fn test_input_variants() {
+        let root = env::temp_dir().join(format!("aoc2020-variants-test-{}", std::process::id()));
+        let (first, second) = (root.join("first"), root.join("second"));
+        fs::create_dir_all(first.join("day7")).unwrap();
+        fs::create_dir_all(second.join("day7")).unwrap();
+        for path in &["first/day7/bob.txt", "first/day7/notes.md", "second/day7.txt",
+                      "second/day7/alice.txt", "second/day7/bob.txt"] {
+            fs::write(root.join(path), "").unwrap();
+        }
+
+        let variants = input_variants_in(&[first.clone(), second], "day7");
+        let none = input_variants_in(&[first], "day8");
+        fs::remove_dir_all(&root).unwrap();
+        assert_eq!(variants, ["day7", "day7/alice", "day7/bob"]);
+        assert!(none.is_empty());
+    }
+
+    #[test]
+    fn test_missing_input_names_tried_paths() {
+        let e = get_input("no_such_day").unwrap_err().to_string();
+        for dir in input_dirs() {
+            assert!(e.contains(&dir.join("no_such_day.txt").display().to_string()), "{}", e);
+        }
+    }
+}